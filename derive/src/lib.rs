@@ -0,0 +1,449 @@
+//! # Commons Derive
+//!
+//! Derive macros for the `commons` crate. Enable them through the
+//! `derive` feature of `euxis-commons` rather than depending on this crate
+//! directly.
+//!
+//! `#[derive(ConfigSchema)]` implements `commons::config::ConfigSchema`
+//! for a struct with named fields. See the documentation of that trait for
+//! the supported `#[config(...)]` attributes.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::parse::ParseStream;
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprLit, Fields, GenericArgument, Lit, LitStr, Meta, Path,
+    PathArguments, PathSegment, Token, Type, parenthesized, parse_macro_input,
+};
+
+/// Derive `commons::config::ConfigSchema` for a struct.
+///
+/// Doc comments become field descriptions, field types determine the
+/// expected value types, and `#[config(...)]` attributes declare defaults,
+/// environment variables and constraints.
+#[proc_macro_derive(ConfigSchema, attributes(config))]
+pub fn derive_config_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Options collected from a field's attributes.
+#[derive(Default)]
+struct FieldOptions {
+    rename: Option<String>,
+    default: Option<Expr>,
+    env: Option<LitStr>,
+    min: Option<f64>,
+    max: Option<f64>,
+    length: Option<(usize, usize)>,
+    one_of: Vec<Expr>,
+    patterns: Vec<Path>,
+    secret: bool,
+    nested: bool,
+    serde_default: bool,
+}
+
+/// Generate the `ConfigSchema` implementation.
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "ConfigSchema can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "ConfigSchema requires named fields",
+        ));
+    };
+
+    let mut krate: Path = syn::parse_quote!(::commons);
+    let mut all_default = false;
+    let mut rename_all = None;
+    for attr in &input.attrs {
+        if attr.path().is_ident("config") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("crate") {
+                    krate = meta.value()?.parse::<LitStr>()?.parse()?;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown config attribute"))
+                }
+            })?;
+        } else if attr.path().is_ident("serde") {
+            let serde = serde_options(attr)?;
+            all_default |= serde.default;
+            rename_all = serde.rename_all.or(rename_all);
+        }
+    }
+
+    let mut statements = Vec::new();
+    for field in &fields.named {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let options = field_options(&field.attrs)?;
+        let key = options.rename.clone().unwrap_or_else(|| {
+            let name = ident.to_string().trim_start_matches("r#").to_string();
+            rename_all
+                .as_ref()
+                .map_or_else(|| name.clone(), |rule| apply_rename_rule(rule, &name))
+        });
+
+        if options.nested {
+            // An optional table may be left out, along with its fields.
+            let (optional, ty) = unwrap_generic(&field.ty, "Option");
+            let nest = if optional {
+                quote!(nest_optional)
+            } else {
+                quote!(nest)
+            };
+            statements.push(quote! {
+                schema.#nest(#key, <#ty as #krate::config::ConfigSchema>::schema())
+            });
+            continue;
+        }
+        let calls = field_calls(&krate, &field.ty, &field.attrs, &options, all_default);
+        statements.push(quote! {
+            schema.field(#krate::config::Field::new(#key) #(#calls)*)
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::config::ConfigSchema for #name #ty_generics #where_clause {
+            fn schema() -> #krate::config::Schema {
+                let schema = #krate::config::Schema::new();
+                #(let schema = #statements;)*
+                schema
+            }
+        }
+    })
+}
+
+/// Builder calls configuring a `Field`.
+fn field_calls(
+    krate: &Path,
+    ty: &Type,
+    attrs: &[Attribute],
+    options: &FieldOptions,
+    all_default: bool,
+) -> Vec<TokenStream2> {
+    let (optional, ty) = unwrap_generic(ty, "Option");
+    let (secret, ty) = unwrap_generic(ty, "Secret");
+
+    let mut calls = Vec::new();
+    if !optional && options.default.is_none() && !options.serde_default && !all_default {
+        calls.push(quote!(.required()));
+    }
+    if let Some(value_type) = value_type(ty) {
+        let variant = syn::Ident::new(value_type, proc_macro2::Span::call_site());
+        calls.push(quote!(.of_type(#krate::config::ValueType::#variant)));
+    }
+    match (options.min, options.max) {
+        (Some(min), Some(max)) => {
+            let (min, max) = (Literal::f64_suffixed(min), Literal::f64_suffixed(max));
+            calls.push(quote!(.range(#min, #max)));
+        }
+        (Some(min), None) => {
+            let min = Literal::f64_suffixed(min);
+            calls.push(quote!(.min(#min)));
+        }
+        (None, Some(max)) => {
+            let max = Literal::f64_suffixed(max);
+            calls.push(quote!(.max(#max)));
+        }
+        (None, None) => {}
+    }
+    if let Some((min, max)) = options.length {
+        let (min, max) = (Literal::usize_suffixed(min), Literal::usize_suffixed(max));
+        calls.push(quote!(.length(#min, #max)));
+    }
+    if !options.one_of.is_empty() {
+        let values = &options.one_of;
+        calls.push(quote!(.one_of([#(#values),*])));
+    }
+    for pattern in &options.patterns {
+        let name = pattern_name(pattern);
+        calls.push(quote!(.pattern(#name, #pattern)));
+    }
+    if options.secret || secret {
+        calls.push(quote!(.secret()));
+    }
+    if let Some(doc) = doc_comment(attrs) {
+        calls.push(quote!(.doc(#doc)));
+    }
+    if let Some(default) = &options.default {
+        calls.push(quote!(.default_value(#default)));
+    }
+    if let Some(env) = &options.env {
+        calls.push(quote!(.env(#env)));
+    }
+    calls
+}
+
+/// Parse the `#[config(...)]` and relevant `#[serde(...)]` attributes of a
+/// field.
+fn field_options(attrs: &[Attribute]) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in attrs {
+        if attr.path().is_ident("serde") {
+            let serde = serde_options(attr)?;
+            if serde.flatten {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "ConfigSchema does not support #[serde(flatten)]; \
+                     use #[config(nested)] on a table field instead",
+                ));
+            }
+            options.serde_default |= serde.default;
+            if options.rename.is_none() {
+                options.rename = serde.rename;
+            }
+            continue;
+        }
+        if !attr.path().is_ident("config") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let path = &meta.path;
+            if path.is_ident("default") {
+                options.default = Some(meta.value()?.parse()?);
+            } else if path.is_ident("env") {
+                options.env = Some(meta.value()?.parse()?);
+            } else if path.is_ident("rename") {
+                options.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if path.is_ident("min") {
+                options.min = Some(parse_number(meta.value()?)?);
+            } else if path.is_ident("max") {
+                options.max = Some(parse_number(meta.value()?)?);
+            } else if path.is_ident("range") {
+                let content;
+                parenthesized!(content in meta.input);
+                options.min = Some(parse_number(&content)?);
+                content.parse::<Token![,]>()?;
+                options.max = Some(parse_number(&content)?);
+            } else if path.is_ident("length") {
+                let content;
+                parenthesized!(content in meta.input);
+                let min = content.parse::<syn::LitInt>()?.base10_parse()?;
+                content.parse::<Token![,]>()?;
+                let max = content.parse::<syn::LitInt>()?.base10_parse()?;
+                options.length = Some((min, max));
+            } else if path.is_ident("one_of") {
+                let content;
+                parenthesized!(content in meta.input);
+                options.one_of = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect();
+            } else if path.is_ident("pattern") {
+                options.patterns.push(meta.value()?.parse()?);
+            } else if path.is_ident("secret") {
+                options.secret = true;
+            } else if path.is_ident("nested") {
+                options.nested = true;
+            } else {
+                return Err(meta.error("unknown config attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// The parts of a `#[serde(...)]` attribute that affect the schema.
+#[derive(Default)]
+struct SerdeOptions {
+    rename: Option<String>,
+    rename_all: Option<LitStr>,
+    default: bool,
+    flatten: bool,
+}
+
+/// Extract `rename = "..."`, `rename_all = "..."` (or its `deserialize`
+/// rule) and whether `default` and `flatten` are present from a
+/// `#[serde(...)]` attribute, skipping everything else.
+fn serde_options(attr: &Attribute) -> syn::Result<SerdeOptions> {
+    let mut options = SerdeOptions::default();
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("rename") && meta.input.peek(Token![=]) {
+            options.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            return Ok(());
+        }
+        if meta.path.is_ident("rename_all") {
+            if meta.input.peek(Token![=]) {
+                options.rename_all = Some(meta.value()?.parse()?);
+            } else {
+                meta.parse_nested_meta(|rule| {
+                    if rule.path.is_ident("deserialize") {
+                        options.rename_all = Some(rule.value()?.parse()?);
+                        Ok(())
+                    } else {
+                        skip_meta(&rule)
+                    }
+                })?;
+            }
+            if let Some(rule) = &options.rename_all
+                && !RENAME_RULES.contains(&rule.value().as_str())
+            {
+                return Err(syn::Error::new_spanned(rule, "unknown rename_all rule"));
+            }
+            return Ok(());
+        }
+        if meta.path.is_ident("default") {
+            options.default = true;
+        } else if meta.path.is_ident("flatten") {
+            options.flatten = true;
+        }
+        skip_meta(&meta)
+    })?;
+    Ok(options)
+}
+
+/// Rules accepted by `#[serde(rename_all = "...")]`.
+const RENAME_RULES: [&str; 8] = [
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+/// Rename a `snake_case` field name the way serde's `rename_all` does.
+fn apply_rename_rule(rule: &LitStr, name: &str) -> String {
+    let pascal = || {
+        name.split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first.to_ascii_uppercase().to_string() + chars.as_str()
+                })
+            })
+            .collect::<String>()
+    };
+    match rule.value().as_str() {
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_ascii_lowercase().to_string() + chars.as_str()
+            })
+        }
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.to_ascii_uppercase().replace('_', "-"),
+        _ => name.to_string(),
+    }
+}
+
+/// Consume the value or argument list of a nested meta item.
+fn skip_meta(meta: &ParseNestedMeta<'_>) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let _content;
+        parenthesized!(_content in meta.input);
+    }
+    Ok(())
+}
+
+/// Parse an optionally negative integer or float literal.
+fn parse_number(input: ParseStream<'_>) -> syn::Result<f64> {
+    let negative = input.parse::<Option<Token![-]>>()?.is_some();
+    let value: f64 = match input.parse::<Lit>()? {
+        Lit::Int(int) => int.base10_parse()?,
+        Lit::Float(float) => float.base10_parse()?,
+        other => return Err(syn::Error::new_spanned(other, "expected a number")),
+    };
+    Ok(if negative { -value } else { value })
+}
+
+/// Field description from its doc comments.
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(doc) => match &doc.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(text),
+                    ..
+                }) => Some(text.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// Name shown for a pattern check: `is_valid_email` becomes `email`.
+fn pattern_name(path: &Path) -> String {
+    let name = path
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+    name.strip_prefix("is_valid_")
+        .or_else(|| name.strip_prefix("is_"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// Last segment of a plain type path.
+fn last_segment(ty: &Type) -> Option<&PathSegment> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+        _ => None,
+    }
+}
+
+/// Strip a single-parameter wrapper such as `Option<T>`, reporting whether
+/// it was present.
+fn unwrap_generic<'a>(ty: &'a Type, wrapper: &str) -> (bool, &'a Type) {
+    if let Some(segment) = last_segment(ty)
+        && segment.ident == wrapper
+        && let PathArguments::AngleBracketed(args) = &segment.arguments
+        && let Some(GenericArgument::Type(inner)) = args.args.first()
+    {
+        return (true, inner);
+    }
+    (false, ty)
+}
+
+/// `ValueType` variant matching a Rust type, when unambiguous.
+fn value_type(ty: &Type) -> Option<&'static str> {
+    let name = last_segment(ty)?.ident.to_string();
+    Some(match name.as_str() {
+        "String" | "PathBuf" | "char" | "IpAddr" | "Ipv4Addr" | "Ipv6Addr" | "SocketAddr" => {
+            "String"
+        }
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+            "Integer"
+        }
+        "f32" | "f64" => "Number",
+        "bool" => "Boolean",
+        "Vec" | "HashSet" | "BTreeSet" => "Array",
+        "HashMap" | "BTreeMap" => "Table",
+        "Datetime" => "Datetime",
+        _ => return None,
+    })
+}
@@ -18,26 +18,102 @@
 //! let config = Config::from_file("config.toml").unwrap();
 //! let app_config: AppConfig = config.parse().unwrap();
 //! ```
+//!
+//! # Layering
+//!
+//! Multiple sources can be stacked with [`LayeredConfig`]; later layers
+//! take precedence over earlier ones:
+//!
+//! ```rust,no_run
+//! use commons::config::{Config, LayeredConfig};
+//!
+//! let config = LayeredConfig::new()
+//!     .with_layer(Config::new("port = 8080"))
+//!     .with_file("/etc/app/config.toml").unwrap()
+//!     .with_optional_file("config.local.toml").unwrap()
+//!     .build();
+//! ```
+
+mod args;
+mod diff;
+mod discovery;
+mod edit;
+mod env_overlay;
+mod format;
+mod include;
+mod interpolate;
+mod layered;
+mod profile;
+mod provenance;
+mod reload;
+mod schema;
+mod secret;
+mod section;
+mod template;
+mod typed;
 
+pub use args::ArgsOverlay;
+pub use diff::{Change, ConfigDiff};
+pub use discovery::ConfigDiscovery;
+pub use env_overlay::EnvOverlay;
+pub use format::ConfigFormat;
+pub use layered::{ArrayMerge, LayeredConfig};
+pub use provenance::Source;
+pub use reload::{ConfigWatcher, ReloadableConfig};
+pub use schema::{Field, Schema, ValueType};
+pub use secret::Secret;
+pub use typed::ConfigSchema;
+
+#[cfg(feature = "derive")]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
+pub use commons_derive::ConfigSchema;
+
+use crate::validation::ValidationError;
 use serde::de::DeserializeOwned;
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::hash::BuildHasher;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Configuration loading and management.
 ///
-/// The TOML content is parsed once on creation and cached internally,
+/// The content is parsed once on creation and cached internally,
 /// so repeated calls to [`get`](Config::get) and [`has_key`](Config::has_key)
-/// are cheap lookups rather than full re-parses.
-#[derive(Debug, Clone)]
+/// are cheap lookups rather than full re-parses. Every supported
+/// [`ConfigFormat`] is normalised into the same TOML value tree, so lookups
+/// behave identically regardless of the source format.
+///
+/// Values of [secret](Config::is_secret) keys are redacted from the
+/// `Debug` output.
+#[derive(Clone)]
 pub struct Config {
-    /// Raw TOML content.
+    /// Raw source content.
     content: String,
     /// Pre-parsed TOML value tree for efficient lookups.
     parsed: toml::Value,
+    /// Format of the raw content.
+    format: ConfigFormat,
+    /// Origin of each leaf value, keyed by dotted path.
+    sources: BTreeMap<String, Source>,
+    /// Keys explicitly marked as secret.
+    secrets: BTreeSet<String>,
+    /// The file as written and its format, when resolving includes or a
+    /// profile changed `content`. Edits are applied to it as well, and it
+    /// is what [`save`](Config::save) writes.
+    document: Option<(String, ConfigFormat)>,
+    /// The profile applied with [`apply_profile`](Config::apply_profile).
+    /// Edits to keys it overrides go to its table in `document`.
+    profile: Option<String>,
 }
 
 impl Config {
     /// Create a new configuration from TOML string content.
     ///
+    /// Malformed TOML yields an empty configuration. Use
+    /// [`try_new`](Config::try_new) to get the syntax error instead.
+    ///
     /// # Arguments
     ///
     /// * `content` - TOML formatted configuration string
@@ -54,23 +130,84 @@ impl Config {
     /// ```
     #[must_use]
     pub fn new(content: &str) -> Self {
-        let parsed =
-            toml::from_str(content).unwrap_or_else(|_| toml::Value::Table(toml::map::Map::new()));
-        Self {
-            content: content.to_string(),
+        let parsed = parse_toml(content).unwrap_or_else(|_| empty_table());
+        Self::from_content(content.to_string(), parsed, ConfigFormat::Toml, |line| {
+            Source::Inline { line }
+        })
+    }
+
+    /// Create a new configuration from TOML string content, failing on
+    /// syntax errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::ParseAt`] with the line, column and offending
+    /// source line if the content is not valid TOML.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{Config, ConfigError};
+    ///
+    /// let err = Config::try_new("name = \"app\"\nport = = 8080").unwrap_err();
+    /// let ConfigError::ParseAt(details) = err else { panic!() };
+    /// assert_eq!(details.line(), Some(2));
+    /// assert_eq!(details.snippet(), Some("port = = 8080"));
+    /// ```
+    pub fn try_new(content: &str) -> Result<Self, ConfigError> {
+        Self::try_new_with_format(content, ConfigFormat::Toml)
+    }
+
+    /// Create a new configuration from content in the given format,
+    /// failing on syntax errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::ParseAt`] if the content is malformed, or
+    /// [`ConfigError::UnsupportedFormat`] if support for `format` is not
+    /// compiled in.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{Config, ConfigFormat};
+    ///
+    /// let config = Config::try_new_with_format("port = 8080", ConfigFormat::Toml).unwrap();
+    /// assert_eq!(config.get::<i64>("port"), Some(8080));
+    /// ```
+    pub fn try_new_with_format(content: &str, format: ConfigFormat) -> Result<Self, ConfigError> {
+        let parsed = format.parse(content)?;
+        Ok(Self::from_content(
+            content.to_string(),
             parsed,
-        }
+            format,
+            |line| Source::Inline { line },
+        ))
     }
 
-    /// Load configuration from a TOML file.
+    /// Load configuration from a file.
+    ///
+    /// The format is detected from the file extension (see
+    /// [`ConfigFormat::from_path`]); files with an unknown extension are
+    /// read as TOML. Malformed content yields an empty configuration. Use
+    /// [`from_file_strict`](Config::from_file_strict) to refuse malformed
+    /// files instead.
+    ///
+    /// Files listed in a top-level `include` key are loaded first, relative
+    /// to the including file, and overridden by it. The `[profile.<name>]`
+    /// table matching [`env::get_environment`](crate::env::get_environment)
+    /// is then applied on top (see [`apply_profile`](Config::apply_profile)).
+    /// `${...}` references are kept as written until
+    /// [`interpolated`](Config::interpolated) is called.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to the TOML configuration file
+    /// * `path` - Path to the configuration file
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be read.
+    /// Returns an error if the file cannot be read, its format is not
+    /// supported, or an include cannot be resolved.
     ///
     /// # Example
     ///
@@ -80,11 +217,144 @@ impl Config {
     /// let config = Config::from_file("config.toml").unwrap();
     /// ```
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
-        let content = std::fs::read_to_string(path.as_ref())
-            .map_err(|e| ConfigError::FileRead(format!("{}: {}", path.as_ref().display(), e)))?;
-        let parsed =
-            toml::from_str(&content).unwrap_or_else(|_| toml::Value::Table(toml::map::Map::new()));
-        Ok(Self { content, parsed })
+        Self::load_file(path.as_ref(), None, false, &mut Vec::new())
+    }
+
+    /// Load configuration from a file, failing on syntax errors.
+    ///
+    /// The format is detected as in [`from_file`](Config::from_file).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::FileRead`] if the file cannot be read, or
+    /// [`ConfigError::ParseAt`] (carrying the file path, line and column) if
+    /// it is malformed. Include errors are returned as for
+    /// [`from_file`](Config::from_file).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use commons::config::Config;
+    ///
+    /// let config = Config::from_file_strict("config.toml").unwrap();
+    /// ```
+    pub fn from_file_strict<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Self::load_file(path.as_ref(), None, true, &mut Vec::new())
+    }
+
+    /// Load configuration from a file in an explicit format, failing on
+    /// syntax errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is malformed, the
+    /// format is not supported, or an include cannot be resolved.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use commons::config::{Config, ConfigFormat};
+    ///
+    /// let config = Config::from_file_with_format("app.conf", ConfigFormat::Toml).unwrap();
+    /// ```
+    pub fn from_file_with_format<P: AsRef<Path>>(
+        path: P,
+        format: ConfigFormat,
+    ) -> Result<Self, ConfigError> {
+        Self::load_file(path.as_ref(), Some(format), true, &mut Vec::new())
+    }
+
+    /// Shared implementation of the file constructors.
+    ///
+    /// Resolves `include` directives and applies the profile for the
+    /// current environment, keeping the file as written for
+    /// [`save`](Config::save). Every file read, or attempted, is recorded
+    /// in `files`.
+    fn load_file(
+        path: &Path,
+        format: Option<ConfigFormat>,
+        strict: bool,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<Self, ConfigError> {
+        files.push(path.to_path_buf());
+        let config = Self::read_file(path, format, strict)?;
+        let document = (config.content.clone(), config.format);
+        let mut config = include::resolve(config, path, strict, &mut Vec::new(), files)?;
+        config.apply_profile(&crate::env::get_environment());
+        if config.content != document.0 {
+            config.document = Some(document);
+        }
+        Ok(config)
+    }
+
+    /// Read and parse a single file.
+    fn read_file(
+        path: &Path,
+        format: Option<ConfigFormat>,
+        strict: bool,
+    ) -> Result<Self, ConfigError> {
+        let format = format.unwrap_or_else(|| ConfigFormat::from_path(path).unwrap_or_default());
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::FileRead(format!("{}: {}", path.display(), e)))?;
+        let parsed = match format.parse(&content) {
+            Ok(parsed) => parsed,
+            Err(ConfigError::ParseAt(_)) if !strict => empty_table(),
+            Err(ConfigError::ParseAt(e)) => {
+                return Err(ConfigError::ParseAt(Box::new(e.with_path(path))));
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Self::from_content(content, parsed, format, |line| {
+            Source::File {
+                path: path.to_path_buf(),
+                line,
+            }
+        }))
+    }
+
+    /// Create a configuration from parsed content, recording `source` with
+    /// the defining line of each key where it is known.
+    fn from_content(
+        content: String,
+        parsed: toml::Value,
+        format: ConfigFormat,
+        source: impl Fn(Option<usize>) -> Source,
+    ) -> Self {
+        let lines = if format == ConfigFormat::Toml {
+            provenance::toml_lines(&content)
+        } else {
+            BTreeMap::new()
+        };
+        let sources = provenance::sources_for(&parsed, |key| source(lines.get(key).copied()));
+        Self {
+            content,
+            parsed,
+            format,
+            sources,
+            secrets: BTreeSet::new(),
+            document: None,
+            profile: None,
+        }
+    }
+
+    /// Create a configuration from an already-built value tree, recording
+    /// the source of each leaf key.
+    fn from_value(parsed: toml::Value, source: impl Fn(&str) -> Source) -> Self {
+        Self {
+            content: toml::to_string_pretty(&parsed).unwrap_or_default(),
+            sources: provenance::sources_for(&parsed, source),
+            parsed,
+            format: ConfigFormat::Toml,
+            secrets: BTreeSet::new(),
+            document: None,
+            profile: None,
+        }
+    }
+
+    /// Format of the raw content.
+    #[must_use]
+    pub const fn format(&self) -> ConfigFormat {
+        self.format
     }
 
     /// Parse the configuration into a typed struct.
@@ -109,7 +379,28 @@ impl Config {
     /// assert_eq!(parsed.name, "test");
     /// ```
     pub fn parse<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
-        toml::from_str(&self.content).map_err(|e| ConfigError::Parse(e.to_string()))
+        // TOML content is deserialized directly so errors can point at the
+        // offending line; other formats go through the normalised tree.
+        if self.format == ConfigFormat::Toml {
+            toml::from_str(&self.content).map_err(|e| {
+                let mut error = ParseError::from_toml(&e, &self.content);
+                let secret = error.message == secret::INVALID_SECRET
+                    || provenance::toml_lines(&self.content)
+                        .iter()
+                        .any(|(key, line)| Some(*line) == error.line && self.is_secret(key));
+                // Deserialization messages may quote the value too.
+                if secret {
+                    error.message = secret::INVALID_SECRET.to_string();
+                    error.redact_snippet();
+                }
+                ConfigError::ParseAt(Box::new(error))
+            })
+        } else {
+            self.parsed
+                .clone()
+                .try_into()
+                .map_err(|e: toml::de::Error| ConfigError::Parse(e.message().trim().to_string()))
+        }
     }
 
     /// Get a value from the configuration by key path.
@@ -139,13 +430,108 @@ impl Config {
         T::from_toml_value(current)
     }
 
+    /// Get a required value by key path.
+    ///
+    /// Collections are converted strictly, as with
+    /// [`get_strict`](Config::get_strict).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::MissingKey`] if the key is absent, or
+    /// [`ConfigError::TypeMismatch`] if the value does not convert to `T`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{Config, ConfigError};
+    ///
+    /// let config = Config::new("port = \"http\"");
+    /// assert!(matches!(config.require::<u16>("host"), Err(ConfigError::MissingKey(_))));
+    /// assert_eq!(
+    ///     config.require::<u16>("port").unwrap_err().to_string(),
+    ///     "Config key `port` has the wrong type: expected integer within u16, found string"
+    /// );
+    /// ```
+    pub fn require<T: FromTomlValue>(&self, key: &str) -> Result<T, ConfigError> {
+        self.get_result(key)?
+            .ok_or_else(|| ConfigError::MissingKey(key.to_string()))
+    }
+
+    /// Get an optional value by key path, failing if it is present but
+    /// does not convert to `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::TypeMismatch`] if the value does not convert.
+    pub fn get_result<T: FromTomlValue>(&self, key: &str) -> Result<Option<T>, ConfigError> {
+        let Some(value) = self.get::<toml::Value>(key) else {
+            return Ok(None);
+        };
+        T::from_toml_value_strict(&value)
+            .map(Some)
+            .ok_or_else(|| ConfigError::TypeMismatch {
+                key: key.to_string(),
+                expected: T::expected_type(),
+                actual: value.type_str(),
+            })
+    }
+
+    /// Get a value by key path, or `default` if it is missing or does not
+    /// convert.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let config = Config::new("workers = 4");
+    /// assert_eq!(config.get_or("workers", 1_usize), 4);
+    /// assert_eq!(config.get_or("threads", 1_usize), 1);
+    /// ```
+    #[must_use]
+    pub fn get_or<T: FromTomlValue>(&self, key: &str, default: T) -> T {
+        self.get(key).unwrap_or(default)
+    }
+
+    /// Get a value by key path, or compute a default if it is missing or
+    /// does not convert.
+    #[must_use]
+    pub fn get_or_else<T: FromTomlValue>(&self, key: &str, default: impl FnOnce() -> T) -> T {
+        self.get(key).unwrap_or_else(default)
+    }
+
+    /// Get a value by key path, rejecting collections with any element
+    /// that does not convert.
+    ///
+    /// [`get`](Config::get) skips such elements instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let config = Config::new("ports = [80, 443, 70000]");
+    /// assert_eq!(config.get::<Vec<u16>>("ports"), Some(vec![80, 443]));
+    /// assert_eq!(config.get_strict::<Vec<u16>>("ports"), None);
+    /// ```
+    #[must_use]
+    pub fn get_strict<T: FromTomlValue>(&self, key: &str) -> Option<T> {
+        T::from_toml_value_strict(self.get::<toml::Value>(key).as_ref()?)
+    }
+
     /// Check if a key exists in the configuration.
     #[must_use]
     pub fn has_key(&self, key: &str) -> bool {
         self.get::<toml::Value>(key).is_some()
     }
 
-    /// Get the raw TOML content.
+    /// Get the raw source content.
+    ///
+    /// Configurations produced by merging or by [`ConfigBuilder`] hold a
+    /// TOML rendering of their values, as do files with `include`
+    /// directives, whose content is the resolved configuration rather than
+    /// the file as written. The content includes secret values;
+    /// use [`redacted`](Config::redacted) for output that may be logged.
     #[must_use]
     pub fn raw(&self) -> &str {
         &self.content
@@ -159,49 +545,383 @@ pub enum ConfigError {
     #[error("Failed to read config file: {0}")]
     FileRead(String),
 
+    /// Failed to write configuration file.
+    #[error("Failed to write config file: {0}")]
+    FileWrite(String),
+
     /// Failed to parse configuration.
     #[error("Failed to parse config: {0}")]
     Parse(String),
 
+    /// Failed to parse configuration, with the location of the error.
+    #[error("Failed to parse config: {0}")]
+    ParseAt(Box<ParseError>),
+
     /// Missing required configuration key.
     #[error("Missing required config key: {0}")]
     MissingKey(String),
+
+    /// Configuration value has the wrong type.
+    #[error("Config key `{key}` has the wrong type: expected {expected}, found {actual}")]
+    TypeMismatch {
+        /// Dotted key of the value.
+        key: String,
+        /// Description of the accepted values.
+        expected: String,
+        /// TOML type of the value found.
+        actual: &'static str,
+    },
+
+    /// Configuration format not supported by this build.
+    #[error("Unsupported config format: {0}")]
+    UnsupportedFormat(String),
+
+    /// Value could not be converted into configuration data.
+    #[error("Failed to serialize config value: {0}")]
+    Serialize(String),
+
+    /// An `include` directive could not be resolved.
+    #[error("Config include failed: {0}")]
+    Include(String),
+
+    /// A `${...}` reference could not be resolved.
+    #[error("Config interpolation failed: {0}")]
+    Interpolation(String),
+
+    /// A command-line override is malformed.
+    #[error("Invalid config override: {0}")]
+    Override(String),
+
+    /// Configuration violates its schema.
+    #[error("Invalid config: {}", format_violations(.0))]
+    Validation(Vec<(String, ValidationError)>),
+}
+
+/// Render schema violations as `key: message` pairs.
+fn format_violations(errors: &[(String, ValidationError)]) -> String {
+    errors
+        .iter()
+        .map(|(key, error)| format!("{key}: {error}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Details of a configuration parse failure.
+///
+/// Carries the location of the error, when known, so that callers can point
+/// users at the offending line of their configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    path: Option<PathBuf>,
+    line: Option<usize>,
+    column: Option<usize>,
+    snippet: Option<String>,
+}
+
+impl ParseError {
+    /// Create a parse error without location information.
+    #[must_use]
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            path: None,
+            line: None,
+            column: None,
+            snippet: None,
+        }
+    }
+
+    /// Build a parse error from a TOML error and the content it refers to.
+    fn from_toml(err: &toml::de::Error, content: &str) -> Self {
+        let error = Self::new(err.message().trim());
+        match err.span() {
+            Some(span) => error.at_offset(content, span.start),
+            None => error,
+        }
+    }
+
+    /// Attach the line, column and source line for a byte offset.
+    fn at_offset(mut self, content: &str, offset: usize) -> Self {
+        let offset = offset.min(content.len());
+        let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[offset..]
+            .find('\n')
+            .map_or(content.len(), |i| offset + i);
+        self.line = Some(content[..offset].matches('\n').count() + 1);
+        self.column = Some(content[line_start..offset].chars().count() + 1);
+        self.snippet = Some(
+            content[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+        );
+        if self.snippet.as_deref().is_some_and(secret::defines_secret) {
+            self.redact_snippet();
+        }
+        self
+    }
+
+    /// Replace the value on the source line with `[REDACTED]`.
+    fn redact_snippet(&mut self) {
+        self.snippet = self.snippet.as_deref().map(secret::redact_line);
+    }
+
+    /// Attach the location for a one-based line and column.
+    #[cfg(any(feature = "json", feature = "ini"))]
+    fn at_line_column(self, content: &str, line: usize, column: usize) -> Self {
+        let line_start: usize = content
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let offset = content[line_start.min(content.len())..]
+            .char_indices()
+            .nth(column.saturating_sub(1))
+            .map_or(content.len(), |(i, _)| line_start + i);
+        self.at_offset(content, offset)
+    }
+
+    /// Attach the path of the file being parsed.
+    #[must_use]
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Attach a location recorded earlier, such as in an error report.
+    #[cfg(feature = "error")]
+    pub(crate) fn with_location(
+        mut self,
+        line: Option<usize>,
+        column: Option<usize>,
+        snippet: Option<String>,
+    ) -> Self {
+        self.line = line;
+        self.column = column;
+        self.snippet = snippet;
+        self
+    }
+
+    /// Description of what went wrong.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Path of the file being parsed, if loaded from disk.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// One-based line number of the error.
+    #[must_use]
+    pub const fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// One-based column number of the error.
+    #[must_use]
+    pub const fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// Source line containing the error.
+    #[must_use]
+    pub fn snippet(&self) -> Option<&str> {
+        self.snippet.as_deref()
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.path, self.line, self.column) {
+            (Some(path), Some(line), Some(column)) => {
+                write!(f, "{}:{line}:{column}: ", path.display())?;
+            }
+            (None, Some(line), Some(column)) => write!(f, "line {line}, column {column}: ")?,
+            (Some(path), _, _) => write!(f, "{}: ", path.display())?,
+            (None, _, _) => {}
+        }
+        write!(f, "{}", self.message)?;
+
+        if let (Some(snippet), Some(column)) = (&self.snippet, self.column) {
+            let caret = " ".repeat(column - 1);
+            write!(f, "\n    {snippet}\n    {caret}^")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// An empty root table.
+fn empty_table() -> toml::Value {
+    toml::Value::Table(toml::map::Map::new())
+}
+
+/// Parse TOML document content into a value tree.
+fn parse_toml(content: &str) -> Result<toml::Value, ParseError> {
+    toml::from_str(content).map_err(|e| ParseError::from_toml(&e, content))
 }
 
 /// Trait for converting TOML values to Rust types.
 pub trait FromTomlValue: Sized {
     /// Convert from a TOML value.
     fn from_toml_value(value: &toml::Value) -> Option<Self>;
+
+    /// Convert from a TOML value, failing if any nested element fails.
+    ///
+    /// Collections skip elements that do not convert in
+    /// [`from_toml_value`](FromTomlValue::from_toml_value); here they
+    /// reject the whole value instead. Scalars behave identically.
+    #[must_use]
+    fn from_toml_value_strict(value: &toml::Value) -> Option<Self> {
+        Self::from_toml_value(value)
+    }
+
+    /// Description of the accepted values, used in
+    /// [`ConfigError::TypeMismatch`].
+    #[must_use]
+    fn expected_type() -> String {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
 }
 
 impl FromTomlValue for String {
     fn from_toml_value(value: &toml::Value) -> Option<Self> {
         value.as_str().map(Self::from)
     }
+
+    fn expected_type() -> String {
+        "string".into()
+    }
 }
 
 impl FromTomlValue for i64 {
     fn from_toml_value(value: &toml::Value) -> Option<Self> {
         value.as_integer()
     }
+
+    fn expected_type() -> String {
+        "integer".into()
+    }
+}
+
+/// Integer types other than `i64` reject values outside their range.
+macro_rules! impl_from_toml_int {
+    ($($ty:ty),*) => {
+        $(
+            impl FromTomlValue for $ty {
+                fn from_toml_value(value: &toml::Value) -> Option<Self> {
+                    value.as_integer().and_then(|i| Self::try_from(i).ok())
+                }
+
+                fn expected_type() -> String {
+                    format!("integer within {}", stringify!($ty))
+                }
+            }
+        )*
+    };
 }
 
+impl_from_toml_int!(i8, i16, i32, isize, u8, u16, u32, u64, usize);
+
 impl FromTomlValue for f64 {
     fn from_toml_value(value: &toml::Value) -> Option<Self> {
         value.as_float()
     }
+
+    fn expected_type() -> String {
+        "float".into()
+    }
 }
 
 impl FromTomlValue for bool {
     fn from_toml_value(value: &toml::Value) -> Option<Self> {
         value.as_bool()
     }
+
+    fn expected_type() -> String {
+        "boolean".into()
+    }
 }
 
 impl FromTomlValue for toml::Value {
     fn from_toml_value(value: &toml::Value) -> Option<Self> {
         Some(value.clone())
     }
+
+    fn expected_type() -> String {
+        "any value".into()
+    }
+}
+
+/// Paths have a leading `~` expanded with
+/// [`fs::resolve_path`](crate::fs::resolve_path).
+impl FromTomlValue for PathBuf {
+    fn from_toml_value(value: &toml::Value) -> Option<Self> {
+        value.as_str().map(crate::fs::resolve_path)
+    }
+
+    fn expected_type() -> String {
+        "path string".into()
+    }
+}
+
+/// Durations are read from strings with
+/// [`time::parse_duration`](crate::time::parse_duration) (`"1h 30m"`), or
+/// from non-negative numbers of seconds.
+impl FromTomlValue for Duration {
+    fn from_toml_value(value: &toml::Value) -> Option<Self> {
+        match value {
+            toml::Value::String(s) => crate::time::parse_duration(s).ok(),
+            toml::Value::Integer(secs) => u64::try_from(*secs).ok().map(Self::from_secs),
+            toml::Value::Float(secs) => Self::try_from_secs_f64(*secs).ok(),
+            _ => None,
+        }
+    }
+
+    fn expected_type() -> String {
+        "duration string or seconds".into()
+    }
+}
+
+impl FromTomlValue for IpAddr {
+    fn from_toml_value(value: &toml::Value) -> Option<Self> {
+        value.as_str()?.parse().ok()
+    }
+
+    fn expected_type() -> String {
+        "IP address string".into()
+    }
+}
+
+impl FromTomlValue for SocketAddr {
+    fn from_toml_value(value: &toml::Value) -> Option<Self> {
+        value.as_str()?.parse().ok()
+    }
+
+    fn expected_type() -> String {
+        "socket address string".into()
+    }
+}
+
+/// Distinguishes a missing key (`None` from [`Config::get`]) from a
+/// present value that does not convert (`Some(None)`).
+impl<T: FromTomlValue> FromTomlValue for Option<T> {
+    fn from_toml_value(value: &toml::Value) -> Option<Self> {
+        Some(T::from_toml_value(value))
+    }
+
+    fn from_toml_value_strict(value: &toml::Value) -> Option<Self> {
+        Some(T::from_toml_value_strict(value))
+    }
+
+    fn expected_type() -> String {
+        T::expected_type()
+    }
 }
 
 impl<T: FromTomlValue> FromTomlValue for Vec<T> {
@@ -210,9 +930,186 @@ impl<T: FromTomlValue> FromTomlValue for Vec<T> {
             .as_array()
             .map(|arr| arr.iter().filter_map(T::from_toml_value).collect())
     }
+
+    fn from_toml_value_strict(value: &toml::Value) -> Option<Self> {
+        value
+            .as_array()?
+            .iter()
+            .map(T::from_toml_value_strict)
+            .collect()
+    }
+
+    fn expected_type() -> String {
+        format!("array of {}", T::expected_type())
+    }
+}
+
+impl<T: FromTomlValue, S: BuildHasher + Default> FromTomlValue for HashMap<String, T, S> {
+    fn from_toml_value(value: &toml::Value) -> Option<Self> {
+        value.as_table().map(|table| {
+            table
+                .iter()
+                .filter_map(|(k, v)| T::from_toml_value(v).map(|v| (k.clone(), v)))
+                .collect()
+        })
+    }
+
+    fn from_toml_value_strict(value: &toml::Value) -> Option<Self> {
+        value
+            .as_table()?
+            .iter()
+            .map(|(k, v)| T::from_toml_value_strict(v).map(|v| (k.clone(), v)))
+            .collect()
+    }
+
+    fn expected_type() -> String {
+        format!("table of {}", T::expected_type())
+    }
+}
+
+impl<T: FromTomlValue> FromTomlValue for BTreeMap<String, T> {
+    fn from_toml_value(value: &toml::Value) -> Option<Self> {
+        value.as_table().map(|table| {
+            table
+                .iter()
+                .filter_map(|(k, v)| T::from_toml_value(v).map(|v| (k.clone(), v)))
+                .collect()
+        })
+    }
+
+    fn from_toml_value_strict(value: &toml::Value) -> Option<Self> {
+        value
+            .as_table()?
+            .iter()
+            .map(|(k, v)| T::from_toml_value_strict(v).map(|v| (k.clone(), v)))
+            .collect()
+    }
+
+    fn expected_type() -> String {
+        format!("table of {}", T::expected_type())
+    }
+}
+
+/// Insert `value` at a dotted key path, creating intermediate tables.
+///
+/// Any non-table value found along the path is replaced by a table.
+fn insert_path(table: &mut toml::map::Map<String, toml::Value>, key: &str, value: toml::Value) {
+    let mut current = table;
+    let mut parts = key.split('.').peekable();
+
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            current.insert(part.to_string(), value);
+            return;
+        }
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::map::Map::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::map::Map::new());
+        }
+        let toml::Value::Table(next) = entry else {
+            unreachable!("entry was just made a table");
+        };
+        current = next;
+    }
+}
+
+/// Flatten a value tree into its leaf values keyed by dotted path.
+///
+/// Arrays and empty tables are treated as leaves.
+fn flatten(value: &toml::Value) -> BTreeMap<String, &toml::Value> {
+    fn walk<'a>(prefix: &str, value: &'a toml::Value, out: &mut BTreeMap<String, &'a toml::Value>) {
+        match value {
+            toml::Value::Table(table) if !table.is_empty() => {
+                for (key, child) in table {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{prefix}.{key}")
+                    };
+                    walk(&path, child, out);
+                }
+            }
+            _ if prefix.is_empty() => {}
+            _ => {
+                out.insert(prefix.to_string(), value);
+            }
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    walk("", value, &mut out);
+    out
+}
+
+/// Infer a typed TOML value from a plain string such as an environment
+/// variable value.
+///
+/// Integers, floats, booleans (`true`/`false`/`yes`/`no`/`on`/`off`) and
+/// TOML inline arrays (`[1, 2]`) are recognised; anything else is kept as
+/// a string. Numbers with a leading zero, such as file modes (`0755`) or
+/// zero-padded IDs (`007`), stay strings so the zeros are not lost.
+fn infer_value(raw: &str) -> toml::Value {
+    let trimmed = raw.trim();
+    let digits = trimmed.trim_start_matches(['-', '+']).as_bytes();
+    let leading_zero = digits.len() > 1 && digits[0] == b'0' && digits[1].is_ascii_digit();
+
+    if !leading_zero && let Ok(int) = trimmed.parse::<i64>() {
+        return toml::Value::Integer(int);
+    }
+
+    let numeric = trimmed.bytes().any(|b| b.is_ascii_digit())
+        && trimmed
+            .bytes()
+            .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'-' | b'+' | b'e' | b'E'));
+    if numeric
+        && !leading_zero
+        && let Ok(float) = trimmed.parse::<f64>()
+    {
+        return toml::Value::Float(float);
+    }
+
+    if ["true", "false", "yes", "no", "on", "off"]
+        .iter()
+        .any(|word| trimmed.eq_ignore_ascii_case(word))
+    {
+        return toml::Value::Boolean(crate::env::parse_bool(trimmed));
+    }
+
+    if trimmed.starts_with('[')
+        && trimmed.ends_with(']')
+        && let Ok(mut table) =
+            toml::from_str::<toml::map::Map<String, toml::Value>>(&format!("v = {trimmed}"))
+        && let Some(array) = table.remove("v")
+    {
+        return array;
+    }
+
+    toml::Value::String(raw.to_string())
 }
 
 /// Builder for creating configurations programmatically.
+///
+/// Keys are dotted paths: `set_int("server.port", 8080)` creates a
+/// `server` table holding `port`. Setting a key below an existing
+/// non-table value replaces that value with a table.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::ConfigBuilder;
+///
+/// let config = ConfigBuilder::new()
+///     .set_string("server.host", "localhost")
+///     .set_int("server.port", 8080)
+///     .set_float("server.ratio", 0.5)
+///     .set_array("server.tags", ["a", "b"])
+///     .build();
+///
+/// assert_eq!(config.get::<String>("server.host"), Some("localhost".into()));
+/// assert_eq!(config.get::<Vec<String>>("server.tags").map(|t| t.len()), Some(2));
+/// ```
 #[derive(Debug, Default)]
 pub struct ConfigBuilder {
     values: toml::map::Map<String, toml::Value>,
@@ -227,34 +1124,116 @@ impl ConfigBuilder {
 
     /// Set a string value.
     #[must_use]
-    pub fn set_string(mut self, key: &str, value: &str) -> Self {
-        self.values
-            .insert(key.to_string(), toml::Value::String(value.to_string()));
-        self
+    pub fn set_string(self, key: &str, value: &str) -> Self {
+        self.set_value(key, value)
     }
 
     /// Set an integer value.
     #[must_use]
-    pub fn set_int(mut self, key: &str, value: i64) -> Self {
-        self.values
-            .insert(key.to_string(), toml::Value::Integer(value));
-        self
+    pub fn set_int(self, key: &str, value: i64) -> Self {
+        self.set_value(key, value)
+    }
+
+    /// Set a float value.
+    #[must_use]
+    pub fn set_float(self, key: &str, value: f64) -> Self {
+        self.set_value(key, value)
     }
 
     /// Set a boolean value.
     #[must_use]
-    pub fn set_bool(mut self, key: &str, value: bool) -> Self {
-        self.values
-            .insert(key.to_string(), toml::Value::Boolean(value));
+    pub fn set_bool(self, key: &str, value: bool) -> Self {
+        self.set_value(key, value)
+    }
+
+    /// Set an array value.
+    #[must_use]
+    pub fn set_array<I, V>(self, key: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<toml::Value>,
+    {
+        self.set_value(
+            key,
+            toml::Value::Array(values.into_iter().map(Into::into).collect()),
+        )
+    }
+
+    /// Set a table value, replacing any table already at `key`.
+    #[must_use]
+    pub fn set_table(self, key: &str, table: toml::map::Map<String, toml::Value>) -> Self {
+        self.set_value(key, table)
+    }
+
+    /// Set a TOML date, time or datetime value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::ConfigBuilder;
+    ///
+    /// let released = "1979-05-27T07:32:00Z".parse().unwrap();
+    /// let config = ConfigBuilder::new().set_datetime("release.date", released).build();
+    /// assert!(config.raw().contains("date = 1979-05-27T07:32:00Z"));
+    /// ```
+    #[must_use]
+    pub fn set_datetime(self, key: &str, value: toml::value::Datetime) -> Self {
+        self.set_value(key, value)
+    }
+
+    /// Set any value convertible into a TOML value.
+    #[must_use]
+    pub fn set_value(mut self, key: &str, value: impl Into<toml::Value>) -> Self {
+        insert_path(&mut self.values, key, value.into());
         self
     }
 
+    /// Set a value from any serializable type.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Serialize`] if the value has no TOML
+    /// representation, such as `None` or a map with non-string keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::ConfigBuilder;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Database {
+    ///     url: String,
+    ///     pool: u32,
+    /// }
+    ///
+    /// let database = Database { url: "postgres://localhost".into(), pool: 10 };
+    /// let config = ConfigBuilder::new()
+    ///     .set_serialize("database", &database)
+    ///     .unwrap()
+    ///     .build();
+    /// assert_eq!(config.get::<i64>("database.pool"), Some(10));
+    /// ```
+    pub fn set_serialize<T: serde::Serialize + ?Sized>(
+        self,
+        key: &str,
+        value: &T,
+    ) -> Result<Self, ConfigError> {
+        let value = toml::Value::try_from(value)
+            .map_err(|e| ConfigError::Serialize(format!("{key}: {e}")))?;
+        Ok(self.set_value(key, value))
+    }
+
     /// Build the configuration.
     #[must_use]
     pub fn build(self) -> Config {
-        let parsed = toml::Value::Table(self.values);
-        let content = toml::to_string_pretty(&parsed).unwrap_or_default();
-        Config { content, parsed }
+        Config::from_value(toml::Value::Table(self.values), |_| Source::Builder)
+    }
+}
+
+impl From<ConfigBuilder> for Config {
+    fn from(builder: ConfigBuilder) -> Self {
+        builder.build()
     }
 }
 
@@ -282,6 +1261,77 @@ mod tests {
         assert_eq!(parsed.port, 8080);
     }
 
+    #[test]
+    fn test_try_new_reports_location() {
+        assert!(Config::try_new("name = \"ok\"").is_ok());
+
+        let err = Config::try_new("name = \"app\"\n[server]\nport = = 8080\n").unwrap_err();
+        let ConfigError::ParseAt(details) = &err else {
+            panic!("expected parse error, got {err:?}");
+        };
+        assert_eq!(details.line(), Some(3));
+        assert_eq!(details.column(), Some(8));
+        assert_eq!(details.snippet(), Some("port = = 8080"));
+        assert!(err.to_string().contains("line 3, column 8"));
+
+        // The lenient constructor keeps its historical behaviour.
+        assert!(!Config::new("port = = 1").has_key("port"));
+    }
+
+    #[test]
+    fn test_parse_errors_redact_secret_lines() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Db {
+            #[allow(dead_code)]
+            key: Secret<i64>,
+            #[allow(dead_code)]
+            dsn: i64,
+        }
+
+        let err = Config::try_new(
+            "[db]
+password = \"hunter2\" x
+",
+        )
+        .unwrap_err();
+        let ConfigError::ParseAt(details) = &err else {
+            panic!("expected parse error, got {err:?}");
+        };
+        assert_eq!(details.snippet(), Some("password = [REDACTED]"));
+        assert!(!err.to_string().contains("hunter2"));
+
+        let err = Config::new("key = \"hunter2\"\ndsn = 1\n")
+            .parse::<Db>()
+            .unwrap_err();
+        assert!(!err.to_string().contains("hunter2"), "{err}");
+
+        let mut config = Config::new("key = 1\ndsn = \"postgres://u:hunter2@db\"\n");
+        config.mark_secret("dsn");
+        let err = config.parse::<Db>().unwrap_err();
+        assert!(!err.to_string().contains("hunter2"), "{err}");
+    }
+
+    #[test]
+    fn test_from_file_strict() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.toml");
+        std::fs::write(&path, "port = 8080\nhost = localhost\n").unwrap();
+
+        let err = Config::from_file_strict(&path).unwrap_err();
+        let ConfigError::ParseAt(details) = &err else {
+            panic!("expected parse error, got {err:?}");
+        };
+        assert_eq!(details.path(), Some(path.as_path()));
+        assert_eq!(details.line(), Some(2));
+        assert!(err.to_string().contains("broken.toml:2:8"));
+
+        assert!(Config::from_file(&path).is_ok());
+        assert!(matches!(
+            Config::from_file_strict(dir.path().join("missing.toml")),
+            Err(ConfigError::FileRead(_))
+        ));
+    }
+
     #[test]
     fn test_get_nested_key() {
         let config = Config::new(
@@ -314,6 +1364,143 @@ mod tests {
         assert_eq!(config.get::<Vec<String>>("missing"), None);
     }
 
+    #[test]
+    fn test_get_path_expands_home() {
+        let config = Config::new("cache = \"~/cache\"\nlog = \"/var/log/app\"\n");
+        assert_eq!(
+            config.get::<PathBuf>("cache"),
+            Some(crate::fs::resolve_path("~/cache"))
+        );
+        assert_eq!(
+            config.get::<PathBuf>("log"),
+            Some(PathBuf::from("/var/log/app"))
+        );
+    }
+
+    #[test]
+    fn test_typed_getters() {
+        let config = Config::new(
+            r#"
+            port = 8080
+            big = 5000000000
+            negative = -1
+            timeout = "1m 30s"
+            retry = 2
+            backoff = 0.5
+            host = "127.0.0.1"
+            bind = "0.0.0.0:80"
+            ports = [80, "http", 443]
+            [limits]
+            rps = 100
+            burst = 200
+            name = "default"
+        "#,
+        );
+
+        assert_eq!(config.get::<u16>("port"), Some(8080));
+        assert_eq!(config.get::<i32>("port"), Some(8080));
+        assert_eq!(config.get::<u32>("big"), None);
+        assert_eq!(config.get::<u64>("big"), Some(5_000_000_000));
+        assert_eq!(config.get::<usize>("negative"), None);
+        assert_eq!(
+            config.get::<Duration>("timeout"),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            config.get::<Duration>("retry"),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            config.get::<Duration>("backoff"),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(config.get::<Duration>("negative"), None);
+        assert_eq!(
+            config.get::<IpAddr>("host"),
+            Some(IpAddr::from([127, 0, 0, 1]))
+        );
+        assert_eq!(
+            config.get::<SocketAddr>("bind"),
+            Some(SocketAddr::from(([0, 0, 0, 0], 80)))
+        );
+        assert_eq!(config.get::<IpAddr>("bind"), None);
+
+        assert_eq!(config.get::<Option<u16>>("port"), Some(Some(8080)));
+        assert_eq!(config.get::<Option<u16>>("host"), Some(None));
+        assert_eq!(config.get::<Option<u16>>("missing"), None);
+
+        let limits: HashMap<String, u32> = config.get("limits").unwrap();
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits["rps"], 100);
+        assert_eq!(config.get_strict::<HashMap<String, u32>>("limits"), None);
+        assert_eq!(
+            config
+                .get_strict::<BTreeMap<String, toml::Value>>("limits")
+                .map(|m| m.len()),
+            Some(3)
+        );
+
+        assert_eq!(config.get::<Vec<u16>>("ports"), Some(vec![80, 443]));
+        assert_eq!(config.get_strict::<Vec<u16>>("ports"), None);
+        assert_eq!(config.get_strict::<u16>("port"), Some(8080));
+    }
+
+    #[test]
+    fn test_fallible_getters() {
+        let config = Config::new("port = 8080\nhosts = [\"a\", 1]\nname = \"app\"\n");
+
+        assert_eq!(config.require::<u16>("port").unwrap(), 8080);
+        assert!(matches!(
+            config.require::<u16>("missing"),
+            Err(ConfigError::MissingKey(key)) if key == "missing"
+        ));
+        match config.require::<Vec<String>>("hosts") {
+            Err(ConfigError::TypeMismatch {
+                key,
+                expected,
+                actual,
+            }) => {
+                assert_eq!(key, "hosts");
+                assert_eq!(expected, "array of string");
+                assert_eq!(actual, "array");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(matches!(
+            config.require::<u8>("port"),
+            Err(ConfigError::TypeMismatch { .. })
+        ));
+
+        assert_eq!(
+            config.get_result::<String>("name").unwrap(),
+            Some("app".into())
+        );
+        assert_eq!(config.get_result::<String>("missing").unwrap(), None);
+        assert!(config.get_result::<bool>("name").is_err());
+
+        assert_eq!(config.get_or("port", 80_u16), 8080);
+        assert_eq!(config.get_or("name", 80_u16), 80);
+        assert_eq!(config.get_or_else("missing", || "x".to_string()), "x");
+    }
+
+    #[test]
+    fn test_infer_value() {
+        assert_eq!(infer_value("9000"), toml::Value::Integer(9000));
+        assert_eq!(infer_value("0.5"), toml::Value::Float(0.5));
+        assert_eq!(infer_value("Off"), toml::Value::Boolean(false));
+        assert_eq!(
+            infer_value("[1, 2]"),
+            toml::Value::Array(vec![toml::Value::Integer(1), toml::Value::Integer(2)])
+        );
+        assert_eq!(infer_value("nan"), toml::Value::String("nan".into()));
+        assert_eq!(infer_value("[oops"), toml::Value::String("[oops".into()));
+        assert_eq!(infer_value("0755"), toml::Value::String("0755".into()));
+        assert_eq!(infer_value("007"), toml::Value::String("007".into()));
+        assert_eq!(infer_value("-01.5"), toml::Value::String("-01.5".into()));
+        assert_eq!(infer_value("0"), toml::Value::Integer(0));
+        assert_eq!(infer_value("0.25"), toml::Value::Float(0.25));
+    }
+
     #[test]
     fn test_config_builder() {
         let config = ConfigBuilder::new()
@@ -326,4 +1513,45 @@ mod tests {
         assert_eq!(config.get::<i64>("port"), Some(8080));
         assert_eq!(config.get::<bool>("debug"), Some(true));
     }
+
+    #[test]
+    fn test_config_builder_nested() {
+        let mut tls = toml::map::Map::new();
+        tls.insert("enabled".into(), true.into());
+
+        let config = ConfigBuilder::new()
+            .set_string("server.host", "localhost")
+            .set_int("server.port", 8080)
+            .set_float("server.ratio", 0.5)
+            .set_array("server.ports", [80, 443])
+            .set_table("server.tls", tls)
+            .set_datetime("released", "2024-01-02".parse().unwrap())
+            .set_serialize("limits", &BTreeMap::from([("rps", 100)]))
+            .unwrap()
+            .build();
+
+        assert_eq!(
+            config.get::<String>("server.host"),
+            Some("localhost".into())
+        );
+        assert_eq!(config.get::<i64>("server.port"), Some(8080));
+        assert_eq!(config.get::<f64>("server.ratio"), Some(0.5));
+        assert_eq!(config.get::<Vec<i64>>("server.ports"), Some(vec![80, 443]));
+        assert_eq!(config.get::<bool>("server.tls.enabled"), Some(true));
+        assert_eq!(config.get::<i64>("limits.rps"), Some(100));
+        assert!(!config.has_key("server.host.port"));
+        assert!(matches!(
+            config.get::<toml::Value>("released"),
+            Some(toml::Value::Datetime(_))
+        ));
+
+        // The rendered content round-trips through the TOML parser.
+        let reparsed = Config::try_new(config.raw()).unwrap();
+        assert_eq!(reparsed.get::<i64>("server.port"), Some(8080));
+
+        assert!(matches!(
+            ConfigBuilder::new().set_serialize("missing", &None::<i64>),
+            Err(ConfigError::Serialize(_))
+        ));
+    }
 }
@@ -0,0 +1,344 @@
+//! Command-line overlay for configurations.
+//!
+//! Applies `--set server.port=9000` style arguments over a configuration,
+//! taking precedence over files and environment variables.
+
+use super::{ArrayMerge, Config, ConfigError, Source, infer_value, insert_path};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+
+/// Overlay that turns `key=value` command-line arguments into
+/// configuration values.
+///
+/// Values are read as TOML literals where possible, so `9000`, `true`,
+/// `[1, 2]`, `{ a = 1 }` and `"9000"` (a string) keep their types. Other
+/// values are coerced like environment variables: `yes`/`no` become
+/// booleans and anything else a string.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::{ArgsOverlay, Config};
+///
+/// let mut config = Config::new("[server]\nport = 8080\nhost = \"localhost\"");
+///
+/// ArgsOverlay::new()
+///     .apply_from(
+///         &mut config,
+///         ["--verbose", "--set", "server.port=9000", "--set=server.host=0.0.0.0"],
+///     )
+///     .unwrap();
+///
+/// assert_eq!(config.get::<i64>("server.port"), Some(9000));
+/// assert_eq!(config.get::<String>("server.host"), Some("0.0.0.0".into()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgsOverlay {
+    flag: String,
+}
+
+impl Default for ArgsOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArgsOverlay {
+    /// Create an overlay reading `--set key=value` arguments.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            flag: "--set".to_string(),
+        }
+    }
+
+    /// Set the flag that introduces an override (default: `--set`).
+    ///
+    /// Both `<flag> key=value` and `<flag>=key=value` are recognised.
+    #[must_use]
+    pub fn flag(mut self, flag: &str) -> Self {
+        self.flag = flag.to_string();
+        self
+    }
+
+    /// Build a configuration from the current process arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Override`] if an override is malformed or
+    /// not valid UTF-8.
+    pub fn load(&self) -> Result<Config, ConfigError> {
+        self.load_from(std::env::args_os().skip(1))
+    }
+
+    /// Build a configuration from an explicit argument list.
+    ///
+    /// Arguments other than the override flag are ignored, so the full
+    /// command line can be passed in, including arguments that are not
+    /// valid UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Override`] if an override is malformed or not
+    /// valid UTF-8, or the flag is missing its value.
+    pub fn load_from<I, S>(&self, args: I) -> Result<Config, ConfigError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut pairs = Vec::new();
+        let mut args = args.into_iter();
+        let inline = format!("{}=", self.flag);
+        while let Some(arg) = args.next() {
+            let arg = arg.as_ref();
+            let pair = if arg == self.flag.as_str() {
+                let value = args.next().ok_or_else(|| {
+                    ConfigError::Override(format!("`{}` expects a key=value argument", self.flag))
+                })?;
+                value.as_ref().to_str().map(str::to_string)
+            } else if arg.as_encoded_bytes().starts_with(inline.as_bytes()) {
+                arg.to_str()
+                    .and_then(|arg| arg.strip_prefix(&inline))
+                    .map(str::to_string)
+            } else {
+                continue;
+            };
+            let pair = pair.ok_or_else(|| {
+                ConfigError::Override(format!("`{}` argument is not valid UTF-8", self.flag))
+            })?;
+            pairs.push(pair);
+        }
+        self.parse(pairs)
+    }
+
+    /// Build a configuration from bare `key=value` strings.
+    ///
+    /// When a key is given more than once, the last value wins. Each value
+    /// records the flag and key that set it as its [`Source`], without the
+    /// value itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Override`] if an entry has no `=`, an empty
+    /// key or an empty key segment.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::ArgsOverlay;
+    ///
+    /// let overrides = ArgsOverlay::new()
+    ///     .parse(["workers=4", "tags=[\"a\", \"b\"]", "name=\"42\""])
+    ///     .unwrap();
+    /// assert_eq!(overrides.get::<i64>("workers"), Some(4));
+    /// assert_eq!(overrides.get::<Vec<String>>("tags").map(|t| t.len()), Some(2));
+    /// assert_eq!(overrides.get_strict::<String>("name"), Some("42".into()));
+    ///
+    /// assert!(ArgsOverlay::new().parse(["workers"]).is_err());
+    /// ```
+    pub fn parse<I, S>(&self, pairs: I) -> Result<Config, ConfigError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut values = toml::map::Map::new();
+        let mut origins = BTreeMap::new();
+        for pair in pairs {
+            let (key, value) = split_pair(pair.as_ref())?;
+            insert_path(&mut values, key, parse_literal(value));
+            origins.insert(key.to_string(), format!("{} {key}", self.flag));
+        }
+        // Leaves of an inline table belong to the argument that set the table.
+        Ok(Config::from_value(toml::Value::Table(values), |key| {
+            let arg = std::iter::once(key)
+                .chain(key.rmatch_indices('.').map(|(end, _)| &key[..end]))
+                .find_map(|prefix| origins.get(prefix))
+                .cloned()
+                .unwrap_or_default();
+            Source::Arg { arg }
+        }))
+    }
+
+    /// Override `config` with the current process arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Override`] if an override is malformed;
+    /// `config` is left untouched.
+    pub fn apply(&self, config: &mut Config) -> Result<(), ConfigError> {
+        config.merge(&self.load()?, ArrayMerge::Replace);
+        Ok(())
+    }
+
+    /// Override `config` with an explicit argument list.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Override`] if an override is malformed;
+    /// `config` is left untouched.
+    pub fn apply_from<I, S>(&self, config: &mut Config, args: I) -> Result<(), ConfigError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        config.merge(&self.load_from(args)?, ArrayMerge::Replace);
+        Ok(())
+    }
+}
+
+/// Split a `key=value` override and validate the key.
+fn split_pair(pair: &str) -> Result<(&str, &str), ConfigError> {
+    let Some((key, value)) = pair.split_once('=') else {
+        return Err(ConfigError::Override(format!(
+            "`{pair}` is not of the form key=value"
+        )));
+    };
+    let key = key.trim();
+    let valid = key.split('.').all(|segment| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+    });
+    if !valid {
+        return Err(ConfigError::Override(format!(
+            "`{pair}` has an invalid key `{key}`"
+        )));
+    }
+    Ok((key, value))
+}
+
+/// Read a value as a TOML literal, falling back to type inference.
+fn parse_literal(raw: &str) -> toml::Value {
+    toml::from_str::<toml::map::Map<String, toml::Value>>(&format!("v = {}", raw.trim()))
+        .ok()
+        .and_then(|mut table| table.remove("v"))
+        .unwrap_or_else(|| infer_value(raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literals() {
+        let config = ArgsOverlay::new()
+            .parse([
+                "a.int=9000",
+                "a.float=0.5",
+                "a.bool=on",
+                "a.quoted=\"true\"",
+                "a.table={ x = 1 }",
+                "a.text=hello world",
+                "a.empty=",
+                "a.url=http://host?q=1",
+            ])
+            .unwrap();
+
+        assert_eq!(config.get_strict::<i64>("a.int"), Some(9000));
+        assert_eq!(config.get_strict::<f64>("a.float"), Some(0.5));
+        assert_eq!(config.get_strict::<bool>("a.bool"), Some(true));
+        assert_eq!(config.get_strict::<String>("a.quoted"), Some("true".into()));
+        assert_eq!(config.get::<i64>("a.table.x"), Some(1));
+        assert_eq!(
+            config.source("a.table.x"),
+            Some(&Source::Arg {
+                arg: "--set a.table".into()
+            })
+        );
+        assert_eq!(config.get::<String>("a.text"), Some("hello world".into()));
+        assert_eq!(config.get::<String>("a.empty"), Some(String::new()));
+        assert_eq!(
+            config.get::<String>("a.url"),
+            Some("http://host?q=1".into())
+        );
+        assert_eq!(
+            config.source("a.int"),
+            Some(&Source::Arg {
+                arg: "--set a.int".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_secret_overrides_are_not_explained() {
+        let mut config = Config::new("[db]\nhost = \"localhost\"\npassword = \"\"\n");
+        ArgsOverlay::new()
+            .flag("-c")
+            .apply_from(&mut config, ["-c", "db.password=hunter2"])
+            .unwrap();
+
+        assert_eq!(
+            config.explain("db.password").unwrap(),
+            "db.password = \"[REDACTED]\" (from command-line argument -c db.password)"
+        );
+        assert!(!config.explain_all().contains("hunter2"));
+        assert!(!format!("{config:?}").contains("hunter2"));
+    }
+
+    #[test]
+    fn test_overrides_take_precedence() {
+        let mut config = Config::new("[server]\nport = 8080\nworkers = 2\n");
+        ArgsOverlay::new()
+            .flag("-s")
+            .apply_from(
+                &mut config,
+                [
+                    "app",
+                    "-s",
+                    "server.port=1",
+                    "-s=server.port=2",
+                    "--set",
+                    "x",
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(config.get::<i64>("server.port"), Some(2));
+        assert_eq!(config.get::<i64>("server.workers"), Some(2));
+        assert!(!config.has_key("x"));
+    }
+
+    #[test]
+    fn test_malformed_overrides() {
+        let overlay = ArgsOverlay::new();
+        for pair in ["port", "=1", "a..b=1", "a b=1", ".a=1"] {
+            assert!(
+                matches!(overlay.parse([pair]), Err(ConfigError::Override(_))),
+                "{pair}"
+            );
+        }
+
+        let mut config = Config::new("port = 8080");
+        let err = overlay
+            .apply_from(&mut config, ["--set", "port=1", "--set"])
+            .unwrap_err();
+        assert!(err.to_string().contains("expects a key=value"));
+        assert_eq!(config.get::<i64>("port"), Some(8080));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_arguments() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+
+        let invalid = || OsString::from_vec(b"\xff".to_vec());
+        let overlay = ArgsOverlay::new();
+
+        let config = overlay
+            .load_from([invalid(), "--set".into(), "port=1".into(), invalid()])
+            .unwrap();
+        assert_eq!(config.get::<i64>("port"), Some(1));
+
+        let mut inline = OsString::from("--set=name=");
+        inline.push(invalid());
+        for args in [vec!["--set".into(), invalid()], vec![inline]] {
+            let err = overlay.load_from(args).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Invalid config override: `--set` argument is not valid UTF-8"
+            );
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Structural differences between configurations.
+
+use super::secret::REDACTED;
+use super::{Config, flatten};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Change to a single dotted key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// The key only exists in the new configuration.
+    Added(toml::Value),
+    /// The key only exists in the old configuration.
+    Removed(toml::Value),
+    /// The key exists in both with different values.
+    Changed {
+        /// Value in the old configuration.
+        old: toml::Value,
+        /// Value in the new configuration.
+        new: toml::Value,
+    },
+}
+
+/// Differences between two configurations, keyed by dotted path.
+///
+/// Leaf values are compared; arrays and empty tables count as leaves.
+/// Values of [secret](Config::is_secret) keys are replaced by
+/// `[REDACTED]`, so a diff is always safe to log.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::Config;
+///
+/// let old = Config::new("[server]\nport = 8080\nhost = \"localhost\"");
+/// let new = Config::new("[server]\nport = 9000\ntls = true");
+///
+/// let diff = old.diff(&new);
+/// assert_eq!(diff.len(), 3);
+/// assert_eq!(
+///     diff.to_string(),
+///     "- server.host = \"localhost\"\n~ server.port = 8080 -> 9000\n+ server.tls = true\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigDiff {
+    changes: BTreeMap<String, Change>,
+}
+
+impl ConfigDiff {
+    /// Check whether the configurations are identical.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Number of changed keys.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Change to a dotted key, if any.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Change> {
+        self.changes.get(key)
+    }
+
+    /// Check whether a dotted key changed.
+    #[must_use]
+    pub fn contains(&self, key: &str) -> bool {
+        self.changes.contains_key(key)
+    }
+
+    /// Every change, sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Change)> {
+        self.changes
+            .iter()
+            .map(|(key, change)| (key.as_str(), change))
+    }
+
+    /// Changed keys, sorted.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.changes.keys().map(String::as_str)
+    }
+
+    /// Keys only present in the new configuration, with their values.
+    pub fn added(&self) -> impl Iterator<Item = (&str, &toml::Value)> {
+        self.iter().filter_map(|(key, change)| match change {
+            Change::Added(value) => Some((key, value)),
+            _ => None,
+        })
+    }
+
+    /// Keys only present in the old configuration, with their values.
+    pub fn removed(&self) -> impl Iterator<Item = (&str, &toml::Value)> {
+        self.iter().filter_map(|(key, change)| match change {
+            Change::Removed(value) => Some((key, value)),
+            _ => None,
+        })
+    }
+
+    /// Keys present in both configurations, with old and new values.
+    pub fn changed(&self) -> impl Iterator<Item = (&str, &toml::Value, &toml::Value)> {
+        self.iter().filter_map(|(key, change)| match change {
+            Change::Changed { old, new } => Some((key, old, new)),
+            _ => None,
+        })
+    }
+
+    /// Render the diff as a TOML document with `added`, `removed` and
+    /// `changed` tables.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let diff = Config::new("port = 80").diff(&Config::new("port = 81"));
+    /// assert_eq!(diff.to_toml_string(), "[changed.port]\nnew = 81\nold = 80\n");
+    /// ```
+    #[must_use]
+    pub fn to_toml_string(&self) -> String {
+        toml::to_string_pretty(&self.to_value()).unwrap_or_default()
+    }
+
+    /// Render the diff as a JSON object with `added`, `removed` and
+    /// `changed` members.
+    #[cfg(feature = "json")]
+    #[must_use]
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string_pretty(&super::format::toml_to_json(&self.to_value()))
+            .unwrap_or_default()
+    }
+
+    /// The diff as a value tree. Keys are kept whole rather than nested.
+    fn to_value(&self) -> toml::Value {
+        let mut added = toml::map::Map::new();
+        let mut removed = toml::map::Map::new();
+        let mut changed = toml::map::Map::new();
+        for (key, change) in &self.changes {
+            match change {
+                Change::Added(value) => {
+                    added.insert(key.clone(), value.clone());
+                }
+                Change::Removed(value) => {
+                    removed.insert(key.clone(), value.clone());
+                }
+                Change::Changed { old, new } => {
+                    let mut entry = toml::map::Map::new();
+                    entry.insert("old".into(), old.clone());
+                    entry.insert("new".into(), new.clone());
+                    changed.insert(key.clone(), toml::Value::Table(entry));
+                }
+            }
+        }
+
+        let mut root = toml::map::Map::new();
+        for (name, table) in [("added", added), ("removed", removed), ("changed", changed)] {
+            if !table.is_empty() {
+                root.insert(name.into(), toml::Value::Table(table));
+            }
+        }
+        toml::Value::Table(root)
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    /// One line per key: `+` added, `-` removed, `~` changed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, change) in &self.changes {
+            match change {
+                Change::Added(value) => writeln!(f, "+ {key} = {value}")?,
+                Change::Removed(value) => writeln!(f, "- {key} = {value}")?,
+                Change::Changed { old, new } => writeln!(f, "~ {key} = {old} -> {new}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Compare this configuration (old) with `other` (new).
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> ConfigDiff {
+        let old = flatten(&self.parsed);
+        let new = flatten(&other.parsed);
+        let mut changes = BTreeMap::new();
+
+        for key in old.keys().chain(new.keys()) {
+            if changes.contains_key(key) {
+                continue;
+            }
+            let secret = self.is_secret(key) || other.is_secret(key);
+            let show = |value: &toml::Value| {
+                if secret {
+                    toml::Value::String(REDACTED.into())
+                } else {
+                    value.clone()
+                }
+            };
+            let change = match (old.get(key), new.get(key)) {
+                (Some(a), Some(b)) if a == b => continue,
+                (Some(a), Some(b)) => Change::Changed {
+                    old: show(a),
+                    new: show(b),
+                },
+                (Some(a), None) => Change::Removed(show(a)),
+                (None, Some(b)) => Change::Added(show(b)),
+                (None, None) => continue,
+            };
+            changes.insert(key.clone(), change);
+        }
+        ConfigDiff { changes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configs() -> (Config, Config) {
+        (
+            Config::new(
+                r#"
+                name = "app"
+                hosts = ["a"]
+                [db]
+                password = "old-pass"
+                pool = 5
+            "#,
+            ),
+            Config::new(
+                r#"
+                name = "app"
+                hosts = ["a", "b"]
+                debug = true
+                [db]
+                password = "new-pass"
+            "#,
+            ),
+        )
+    }
+
+    #[test]
+    fn test_diff() {
+        let (old, new) = configs();
+        let diff = old.diff(&new);
+
+        assert_eq!(
+            diff.keys().collect::<Vec<_>>(),
+            ["db.password", "db.pool", "debug", "hosts"]
+        );
+        assert_eq!(diff.added().count(), 1);
+        assert_eq!(
+            diff.removed().collect::<Vec<_>>(),
+            [("db.pool", &toml::Value::Integer(5))]
+        );
+        assert_eq!(diff.changed().count(), 2);
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(new.diff(&old).added().count(), 1);
+
+        let text = diff.to_string();
+        assert_eq!(
+            text,
+            "~ db.password = \"[REDACTED]\" -> \"[REDACTED]\"\n\
+             - db.pool = 5\n\
+             + debug = true\n\
+             ~ hosts = [\"a\"] -> [\"a\", \"b\"]\n"
+        );
+    }
+
+    #[test]
+    fn test_machine_readable() {
+        let (old, new) = configs();
+        let diff = old.diff(&new);
+
+        let toml = diff.to_toml_string();
+        assert!(!toml.contains("pass\""));
+        let parsed = Config::try_new(&toml).unwrap();
+        assert_eq!(parsed.get::<bool>("added.debug"), Some(true));
+        assert_eq!(
+            parsed
+                .get::<toml::Value>("changed")
+                .and_then(|c| c.get("hosts").and_then(|h| h.get("new")).cloned()),
+            Some(toml::Value::Array(vec!["a".into(), "b".into()]))
+        );
+
+        #[cfg(feature = "json")]
+        {
+            let json: serde_json::Value = serde_json::from_str(&diff.to_json_string()).unwrap();
+            assert_eq!(json["removed"]["db.pool"], 5);
+            assert_eq!(json["changed"]["db.password"]["new"], REDACTED);
+        }
+    }
+}
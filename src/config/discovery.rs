@@ -0,0 +1,343 @@
+//! Configuration file discovery.
+//!
+//! Looks for an application's configuration in the conventional places:
+//!
+//! 1. `/etc/<app>/`
+//! 2. `$XDG_CONFIG_DIRS/<app>/` (default `/etc/xdg`)
+//! 3. `~/.config/<app>/`
+//! 4. `$XDG_CONFIG_HOME/<app>/`
+//! 5. a project file such as `<app>.toml` in the current directory or the
+//!    nearest parent that has one
+//!
+//! Paths are listed lowest precedence first, ready for layering. The system
+//! and user locations can be moved under another directory with
+//! [`ConfigDiscovery::root`].
+
+use super::{Config, ConfigError, LayeredConfig};
+use crate::fs::resolve_path;
+use std::path::{Component, Path, PathBuf};
+
+/// Search for an application's configuration files.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::ConfigDiscovery;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// std::fs::write(dir.path().join("myapp.toml"), "port = 9000").unwrap();
+/// let nested = dir.path().join("src/bin");
+/// std::fs::create_dir_all(&nested).unwrap();
+///
+/// // Rooted at the temporary directory, so system and user files on the
+/// // host are not picked up.
+/// let discovery = ConfigDiscovery::new("myapp")
+///     .start_dir(&nested)
+///     .root(dir.path());
+/// assert_eq!(discovery.find_project_file(), Some(dir.path().join("myapp.toml")));
+///
+/// let config = discovery.load().unwrap();
+/// assert_eq!(config.get::<i64>("port"), Some(9000));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiscovery {
+    app: String,
+    file_name: String,
+    project_files: Vec<String>,
+    start_dir: Option<PathBuf>,
+    root: Option<PathBuf>,
+    strict: bool,
+}
+
+impl ConfigDiscovery {
+    /// Search for the configuration of `app`.
+    ///
+    /// Looks for `config.toml` in the system and user directories, and for
+    /// `<app>.toml` or `.<app>.toml` when walking up from the current
+    /// directory.
+    #[must_use]
+    pub fn new(app: &str) -> Self {
+        Self {
+            app: app.to_string(),
+            file_name: "config.toml".to_string(),
+            project_files: vec![format!("{app}.toml"), format!(".{app}.toml")],
+            start_dir: None,
+            root: None,
+            strict: false,
+        }
+    }
+
+    /// Set the file name looked up in the system and user directories
+    /// (default: `config.toml`).
+    #[must_use]
+    pub fn file_name(mut self, name: &str) -> Self {
+        self.file_name = name.to_string();
+        self
+    }
+
+    /// Set the file names that mark a project-level configuration, in
+    /// order of preference.
+    #[must_use]
+    pub fn project_files(mut self, names: &[&str]) -> Self {
+        self.project_files = names.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Walk up from `dir` instead of the current directory.
+    #[must_use]
+    pub fn start_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.start_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Look for the system and user locations under `dir` instead of the
+    /// filesystem root, so `/etc/<app>/` becomes `<dir>/etc/<app>/`.
+    ///
+    /// Useful for container images and test fixtures. The project file
+    /// search is not affected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::ConfigDiscovery;
+    /// use std::path::PathBuf;
+    ///
+    /// let paths = ConfigDiscovery::new("myapp")
+    ///     .root("/srv/image")
+    ///     .search_paths_with(|_| None);
+    /// assert_eq!(paths[0], PathBuf::from("/srv/image/etc/myapp/config.toml"));
+    /// ```
+    #[must_use]
+    pub fn root(mut self, dir: impl AsRef<Path>) -> Self {
+        self.root = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Refuse malformed files when [loading](ConfigDiscovery::load).
+    #[must_use]
+    pub const fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// System and user locations searched, lowest precedence first,
+    /// whether or not they exist.
+    ///
+    /// The project file is not included; see
+    /// [`find_project_file`](ConfigDiscovery::find_project_file).
+    #[must_use]
+    pub fn search_paths(&self) -> Vec<PathBuf> {
+        self.search_paths_with(|var| std::env::var(var).ok())
+    }
+
+    /// Like [`search_paths`](ConfigDiscovery::search_paths), reading
+    /// `XDG_*` variables through `env`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::ConfigDiscovery;
+    /// use std::path::PathBuf;
+    ///
+    /// let paths = ConfigDiscovery::new("myapp").search_paths_with(|var| match var {
+    ///     "XDG_CONFIG_HOME" => Some("/home/me/.xdg".into()),
+    ///     "XDG_CONFIG_DIRS" => Some("/opt/xdg:relative/ignored".into()),
+    ///     _ => None,
+    /// });
+    ///
+    /// assert_eq!(paths[0], PathBuf::from("/etc/myapp/config.toml"));
+    /// assert_eq!(paths[1], PathBuf::from("/opt/xdg/myapp/config.toml"));
+    /// assert_eq!(paths.last(), Some(&PathBuf::from("/home/me/.xdg/myapp/config.toml")));
+    /// ```
+    #[must_use]
+    pub fn search_paths_with(&self, env: impl Fn(&str) -> Option<String>) -> Vec<PathBuf> {
+        let mut dirs = vec![PathBuf::from("/etc")];
+
+        // XDG_CONFIG_DIRS lists the most important directory first.
+        let system = env("XDG_CONFIG_DIRS")
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| "/etc/xdg".to_string());
+        let mut system: Vec<PathBuf> = std::env::split_paths(&system).map(resolve_path).collect();
+        system.reverse();
+        dirs.extend(system);
+
+        dirs.push(resolve_path("~/.config"));
+        if let Some(home) = env("XDG_CONFIG_HOME").filter(|value| !value.is_empty()) {
+            dirs.push(resolve_path(home));
+        }
+
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for dir in dirs {
+            // The XDG specification requires absolute paths.
+            if !dir.is_absolute() {
+                continue;
+            }
+            let dir = match &self.root {
+                Some(root) => root.join(relative_to_root(&dir)),
+                None => dir,
+            };
+            let path = dir.join(&self.app).join(&self.file_name);
+            paths.retain(|existing| *existing != path);
+            paths.push(path);
+        }
+        paths
+    }
+
+    /// Nearest project file, walking up from the start directory.
+    #[must_use]
+    pub fn find_project_file(&self) -> Option<PathBuf> {
+        let start = self
+            .start_dir
+            .clone()
+            .or_else(|| std::env::current_dir().ok())?;
+        start.ancestors().find_map(|dir| {
+            self.project_files
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file())
+        })
+    }
+
+    /// Every configuration file found, lowest precedence first.
+    ///
+    /// Existing [`search_paths`](ConfigDiscovery::search_paths) come first,
+    /// followed by the [project file](ConfigDiscovery::find_project_file).
+    #[must_use]
+    pub fn find_all(&self) -> Vec<PathBuf> {
+        let mut found: Vec<PathBuf> = self
+            .search_paths()
+            .into_iter()
+            .filter(|path| path.is_file())
+            .collect();
+        found.extend(self.find_project_file());
+        found
+    }
+
+    /// Load and layer every file found.
+    ///
+    /// Returns an empty configuration if no file exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read, or if it is malformed and
+    /// [`strict`](ConfigDiscovery::strict) mode is enabled.
+    pub fn load(&self) -> Result<Config, ConfigError> {
+        self.find_all()
+            .into_iter()
+            .try_fold(LayeredConfig::new().strict(self.strict), |layers, path| {
+                layers.with_file(path)
+            })
+            .map(LayeredConfig::build)
+    }
+}
+
+impl Config {
+    /// Load and layer the configuration files of `app` from the standard
+    /// locations.
+    ///
+    /// Shorthand for [`ConfigDiscovery::new(app).load()`](ConfigDiscovery::load).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a discovered file cannot be read.
+    pub fn discover(app: &str) -> Result<Self, ConfigError> {
+        ConfigDiscovery::new(app).load()
+    }
+}
+
+/// An absolute path with its root and any drive prefix removed.
+fn relative_to_root(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::RootDir | Component::Prefix(_)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_paths() {
+        let discovery = ConfigDiscovery::new("app").file_name("app.yaml");
+        let paths = discovery.search_paths_with(|_| None);
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("/etc/app/app.yaml"),
+                PathBuf::from("/etc/xdg/app/app.yaml"),
+                resolve_path("~/.config/app/app.yaml"),
+            ]
+        );
+
+        let home = resolve_path("~/.config").display().to_string();
+        let paths =
+            discovery.search_paths_with(|var| (var == "XDG_CONFIG_HOME").then(|| home.clone()));
+        assert_eq!(paths.len(), 3);
+
+        let root = tempfile::tempdir().unwrap();
+        let paths = discovery
+            .root(root.path())
+            .search_paths_with(|var| (var == "XDG_CONFIG_HOME").then(|| "/xdg".into()));
+        assert_eq!(paths[0], root.path().join("etc/app/app.yaml"));
+        assert_eq!(paths.last(), Some(&root.path().join("xdg/app/app.yaml")));
+        assert!(paths.iter().all(|path| path.starts_with(root.path())));
+    }
+
+    #[test]
+    fn test_load_layers_files_under_root() {
+        let root = tempfile::tempdir().unwrap();
+        let discovery = ConfigDiscovery::new("tool")
+            .root(root.path())
+            .start_dir(root.path());
+        let paths = discovery.search_paths_with(|_| None);
+        for (path, content) in [
+            (&paths[0], "level = \"system\"\nport = 80\n"),
+            (&paths[2], "level = \"user\"\n"),
+        ] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        assert_eq!(
+            discovery.find_all()[..2],
+            [paths[0].clone(), paths[2].clone()]
+        );
+        let config = discovery.load().unwrap();
+        assert_eq!(config.get::<String>("level"), Some("user".into()));
+        assert_eq!(config.get::<i64>("port"), Some(80));
+    }
+
+    #[test]
+    fn test_project_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a/b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(dir.path().join(".tool.toml"), "level = \"root\"").unwrap();
+        std::fs::write(dir.path().join("a/tool.toml"), "level = \"a\"").unwrap();
+
+        let discovery = ConfigDiscovery::new("tool")
+            .root(dir.path())
+            .start_dir(&nested);
+        assert_eq!(
+            discovery.find_project_file(),
+            Some(dir.path().join("a/tool.toml"))
+        );
+        assert_eq!(
+            discovery
+                .clone()
+                .project_files(&[".tool.toml"])
+                .find_project_file(),
+            Some(dir.path().join(".tool.toml"))
+        );
+        assert_eq!(
+            discovery.load().unwrap().get::<String>("level"),
+            Some("a".into())
+        );
+
+        let empty = ConfigDiscovery::new("commons-missing-app")
+            .root(dir.path())
+            .start_dir(dir.path());
+        assert!(empty.find_all().is_empty());
+        assert!(!empty.load().unwrap().has_key("level"));
+    }
+}
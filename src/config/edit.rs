@@ -0,0 +1,450 @@
+//! In-place editing of configurations.
+//!
+//! Mutations update the value tree and the raw content together. TOML
+//! content is edited through [`toml_edit`], so comments, whitespace and key
+//! order of the original document survive a [`Config::save`]. Other formats
+//! are re-rendered from the value tree.
+//!
+//! A file loaded with [`Config::from_file`] is saved as written: its
+//! `include` directives and profile sections are kept, and only the edited
+//! keys change, rather than the resolved values being written out.
+
+use super::{Config, ConfigError, ConfigFormat, Source, insert_path};
+use std::path::Path;
+use toml_edit::{DocumentMut, InlineTable, Item, Table, TableLike};
+
+impl Config {
+    /// Set the value at a dotted key, creating intermediate tables.
+    ///
+    /// Any non-table value found along the path is replaced by a table.
+    /// For TOML content, the comments and layout of the rest of the
+    /// document are preserved, as is any comment trailing a replaced value.
+    ///
+    /// When the configuration was loaded from a file whose active
+    /// `[profile.<name>]` table overrides `key`, [`save`](Config::save)
+    /// writes the new value into that table, so it is still in effect when
+    /// the file is loaded again.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let mut config = Config::new("# Server settings\n[server]\nport = 8080 # default\n");
+    /// config.set("server.port", 9000);
+    /// config.set("server.tls.enabled", true);
+    ///
+    /// assert_eq!(config.get::<i64>("server.port"), Some(9000));
+    /// assert!(config.raw().contains("# Server settings"));
+    /// assert!(config.raw().contains("port = 9000 # default"));
+    /// ```
+    pub fn set(&mut self, key: &str, value: impl Into<toml::Value>) {
+        let value = value.into();
+        if let toml::Value::Table(root) = &mut self.parsed {
+            insert_path(root, key, value.clone());
+        } else {
+            let mut root = toml::map::Map::new();
+            insert_path(&mut root, key, value.clone());
+            self.parsed = toml::Value::Table(root);
+        }
+        self.sync_content(|doc| set_in_document(doc, key, &value));
+        let profile_key = self.profile_key(key);
+        self.sync_document(|doc| {
+            match profile_key.filter(|profile_key| document_has(doc, profile_key)) {
+                Some(profile_key) => set_in_document(doc, &profile_key, &value),
+                None => set_in_document(doc, key, &value),
+            }
+        });
+        self.record_source(key, &Source::Runtime);
+    }
+
+    /// Remove the value at a dotted key, returning it if it was present.
+    ///
+    /// Tables left empty by the removal are kept. The key is also removed
+    /// from the active profile's table in the file as written (see
+    /// [`set`](Config::set)).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let mut config = Config::new("[server]\nport = 8080\nhost = \"localhost\"\n");
+    /// assert_eq!(config.remove("server.port"), Some(toml::Value::Integer(8080)));
+    /// assert_eq!(config.remove("server.port"), None);
+    /// assert!(config.has_key("server.host"));
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<toml::Value> {
+        let removed = remove_path(&mut self.parsed, key)?;
+        self.sync_content(|doc| remove_from_document(doc, key));
+        let profile_key = self.profile_key(key);
+        self.sync_document(|doc| {
+            remove_from_document(doc, key);
+            if let Some(profile_key) = profile_key {
+                remove_from_document(doc, &profile_key);
+            }
+        });
+        self.forget_sources(key);
+        Some(removed)
+    }
+
+    /// Write the configuration to a file in its own format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::FileWrite`] if the file cannot be written.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use commons::config::Config;
+    ///
+    /// let mut config = Config::from_file_strict("config.toml").unwrap();
+    /// config.set("server.port", 9000);
+    /// config.save("config.toml").unwrap();
+    /// ```
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let content = self.document.as_ref().map_or(&self.content, |(doc, _)| doc);
+        std::fs::write(path, content)
+            .map_err(|e| ConfigError::FileWrite(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Bring the raw content in line with the value tree after an edit.
+    ///
+    /// TOML content is edited in place with `edit`; if it cannot be parsed
+    /// (a lenient load of a malformed file), it is re-rendered instead.
+    pub(super) fn sync_content(&mut self, edit: impl FnOnce(&mut DocumentMut)) {
+        self.content = match self.format {
+            ConfigFormat::Toml => match self.content.parse::<DocumentMut>() {
+                Ok(mut doc) => {
+                    edit(&mut doc);
+                    doc.to_string()
+                }
+                Err(_) => toml::to_string_pretty(&self.parsed).unwrap_or_default(),
+            },
+            format => format.render(&self.parsed),
+        };
+    }
+
+    /// Apply an edit to the file as written, if it is kept apart from the
+    /// resolved content.
+    ///
+    /// Documents in formats other than TOML are edited through a TOML
+    /// rendering of their values and rendered back.
+    fn sync_document(&mut self, edit: impl FnOnce(&mut DocumentMut)) {
+        let Some((content, format)) = &mut self.document else {
+            return;
+        };
+        let edited = if *format == ConfigFormat::Toml {
+            content.parse::<DocumentMut>().ok().map(|mut doc| {
+                edit(&mut doc);
+                doc.to_string()
+            })
+        } else {
+            format
+                .parse(content)
+                .ok()
+                .and_then(|value| toml::to_string(&value).ok())
+                .and_then(|rendered| rendered.parse::<DocumentMut>().ok())
+                .and_then(|mut doc| {
+                    edit(&mut doc);
+                    toml::from_str(&doc.to_string()).ok()
+                })
+                .map(|value: toml::Value| format.render(&value))
+        };
+        if let Some(edited) = edited {
+            *content = edited;
+        }
+    }
+}
+
+/// Remove the value at a dotted key from a value tree.
+fn remove_path(value: &mut toml::Value, key: &str) -> Option<toml::Value> {
+    let (parents, last) = match key.rsplit_once('.') {
+        Some((parents, last)) => (Some(parents), last),
+        None => (None, key),
+    };
+    let mut current = value;
+    for part in parents.into_iter().flat_map(|p| p.split('.')) {
+        current = current.get_mut(part)?;
+    }
+    current.as_table_mut()?.remove(last)
+}
+
+/// Set a dotted key in a TOML document, creating implicit tables.
+pub(super) fn set_in_document(doc: &mut DocumentMut, key: &str, value: &toml::Value) {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or_default();
+
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    let mut inline = false;
+    for part in parts {
+        let item = table
+            .entry(part)
+            .or_insert_with(|| empty_table_item(inline));
+        if !item.is_table_like() {
+            *item = empty_table_item(inline);
+        }
+        inline |= item.is_inline_table();
+        let Some(next) = item.as_table_like_mut() else {
+            unreachable!("item was just made a table");
+        };
+        table = next;
+    }
+
+    let mut item = value_to_item(value, inline);
+    match table.get_mut(last) {
+        // Replace in place so the key and its comments are kept.
+        Some(slot) => {
+            if let Some(old) = slot.as_value()
+                && let Some(new) = item.as_value_mut()
+            {
+                *new.decor_mut() = old.decor().clone();
+            }
+            *slot = item;
+        }
+        None => {
+            table.insert(last, item);
+        }
+    }
+}
+
+/// Remove a dotted key from a TOML document.
+fn remove_from_document(doc: &mut DocumentMut, key: &str) {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or_default();
+
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for part in parts {
+        match table.get_mut(part).and_then(Item::as_table_like_mut) {
+            Some(next) => table = next,
+            None => return,
+        }
+    }
+    table.remove(last);
+}
+
+/// Whether a dotted key is present in a TOML document.
+fn document_has(doc: &DocumentMut, key: &str) -> bool {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or_default();
+
+    let mut table: &dyn TableLike = doc.as_table();
+    for part in parts {
+        match table.get(part).and_then(Item::as_table_like) {
+            Some(next) => table = next,
+            None => return false,
+        }
+    }
+    table.contains_key(last)
+}
+
+/// Empty table to create along a path, matching the parent's style.
+fn empty_table_item(inline: bool) -> Item {
+    if inline {
+        Item::Value(InlineTable::new().into())
+    } else {
+        let mut table = Table::new();
+        table.set_implicit(true);
+        Item::Table(table)
+    }
+}
+
+/// Convert a value into a document item.
+///
+/// Tables become standard `[section]` tables unless they are nested in an
+/// inline table.
+fn value_to_item(value: &toml::Value, inline: bool) -> Item {
+    let Ok(value) = value.to_string().parse::<toml_edit::Value>() else {
+        unreachable!("rendered TOML values always parse");
+    };
+    match value {
+        toml_edit::Value::InlineTable(table) if !inline => Item::Table(table.into_table()),
+        value => Item::Value(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"# Application settings
+name = "app" # display name
+
+# Network
+[server]
+host = "localhost"
+port = 8080 # default port
+
+[logging]
+level = "info"
+"#;
+
+    #[test]
+    fn test_set_preserves_layout() {
+        let mut config = Config::new(DOCUMENT);
+        config.set("server.port", 9000);
+        config.set("name", "service");
+        config.set("server.tags", vec!["a", "b"]);
+
+        assert_eq!(config.get::<i64>("server.port"), Some(9000));
+        assert_eq!(
+            config.raw(),
+            r#"# Application settings
+name = "service" # display name
+
+# Network
+[server]
+host = "localhost"
+port = 9000 # default port
+tags = ["a", "b"]
+
+[logging]
+level = "info"
+"#
+        );
+        // The edited content parses back to the same values.
+        let reparsed = Config::try_new(config.raw()).unwrap();
+        assert_eq!(reparsed.get::<Vec<String>>("server.tags").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_set_creates_tables() {
+        let mut config = Config::new("port = 8080\n");
+        config.set("database.pool.size", 10);
+        config.set("port.http", 80);
+
+        assert_eq!(config.get::<i64>("database.pool.size"), Some(10));
+        assert_eq!(config.get::<i64>("port.http"), Some(80));
+        assert!(config.raw().contains("[database.pool]\nsize = 10"));
+
+        let mut table = toml::map::Map::new();
+        table.insert("user".into(), "admin".into());
+        config.set("auth", table);
+        assert!(config.raw().contains("[auth]\nuser = \"admin\""));
+
+        let mut inline = Config::new("server = { port = 8080 }\n");
+        inline.set("server.tls.enabled", true);
+        assert_eq!(
+            inline.raw(),
+            "server = { port = 8080 , tls = { enabled = true } }\n"
+        );
+        assert_eq!(inline.get::<bool>("server.tls.enabled"), Some(true));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut config = Config::new(DOCUMENT);
+        assert_eq!(
+            config.remove("server.port"),
+            Some(toml::Value::Integer(8080))
+        );
+        assert_eq!(config.remove("server.missing"), None);
+        assert_eq!(config.remove("missing.key"), None);
+        assert!(config.remove("logging").is_some());
+
+        assert!(!config.has_key("server.port"));
+        assert!(!config.raw().contains("port"));
+        assert!(!config.raw().contains("[logging]"));
+        assert!(config.raw().contains("# Network"));
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, DOCUMENT).unwrap();
+
+        let mut config = Config::from_file_strict(&path).unwrap();
+        config.set("logging.level", "debug");
+        config.save(&path).unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(saved, DOCUMENT.replace("\"info\"", "\"debug\""));
+
+        let missing = dir.path().join("missing/app.toml");
+        assert!(matches!(
+            config.save(&missing),
+            Err(ConfigError::FileWrite(_))
+        ));
+    }
+
+    #[test]
+    fn test_save_keeps_includes_and_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("base.toml"),
+            "[server]\nhost = \"localhost\"\n",
+        )
+        .unwrap();
+        let document = format!(
+            "# Application\ninclude = \"base.toml\"\n\n[server]\nport = 8080 # default\n\n\
+             [profile.{}.server]\nport = 80\n",
+            crate::env::get_environment()
+        );
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, &document).unwrap();
+
+        let mut config = Config::from_file_strict(&path).unwrap();
+        assert_eq!(config.get::<i64>("server.port"), Some(80));
+        config.set("server.workers", 4);
+        config.save(&path).unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            saved,
+            document.replace("# default\n", "# default\nworkers = 4\n")
+        );
+        assert!(!saved.contains("localhost"));
+
+        // Keys the profile overrides are edited in the profile.
+        config.set("server.port", 81);
+        config.save(&path).unwrap();
+        let reloaded = Config::from_file_strict(&path).unwrap();
+        assert_eq!(reloaded.get::<i64>("server.port"), Some(81));
+        assert!(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .contains("port = 8080 # default")
+        );
+
+        config.remove("server.port");
+        config.save(&path).unwrap();
+        let reloaded = Config::from_file_strict(&path).unwrap();
+        assert!(!reloaded.has_key("server.port"));
+
+        #[cfg(feature = "json")]
+        {
+            let path = dir.path().join("app.json");
+            std::fs::write(&path, r#"{"include": "base.toml", "port": 8080}"#).unwrap();
+            let mut config = Config::from_file_strict(&path).unwrap();
+            config.set("port", 9000);
+            config.save(&path).unwrap();
+
+            let saved = Config::from_file_with_format(&path, ConfigFormat::Json).unwrap();
+            assert_eq!(saved.get::<i64>("port"), Some(9000));
+            assert!(
+                std::fs::read_to_string(&path)
+                    .unwrap()
+                    .contains("base.toml")
+            );
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_set_rerenders_other_formats() {
+        let mut config =
+            Config::try_new_with_format(r#"{"server": {"port": 8080}}"#, ConfigFormat::Json)
+                .unwrap();
+        config.set("server.host", "localhost");
+
+        let reparsed = Config::try_new_with_format(config.raw(), ConfigFormat::Json).unwrap();
+        assert_eq!(reparsed.get::<i64>("server.port"), Some(8080));
+        assert_eq!(
+            reparsed.get::<String>("server.host"),
+            Some("localhost".into())
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Environment-variable overlay for configurations.
+//!
+//! Maps prefixed environment variables onto dotted configuration keys, so
+//! that `APP_SERVER__PORT=9000` overrides `server.port`.
+
+use super::{ArrayMerge, Config, Source, infer_value, insert_path};
+use crate::env::parse_list;
+use std::collections::BTreeMap;
+
+/// Overlay that turns prefixed environment variables into configuration
+/// values.
+///
+/// Variable names are stripped of the prefix, split on the nesting
+/// separator (`__` by default) and lowercased to form the dotted key.
+/// Values are coerced into TOML integers, floats, booleans and arrays where
+/// possible, and kept as strings otherwise.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::{Config, EnvOverlay};
+///
+/// let overlay = EnvOverlay::new("APP").list_delimiter(",");
+/// let mut config = Config::new("[server]\nport = 8080");
+///
+/// overlay.apply_from(
+///     &mut config,
+///     [
+///         ("APP_SERVER__PORT", "9000"),
+///         ("APP_SERVER__HOSTS", "a.example.com, b.example.com"),
+///         ("OTHER_VAR", "ignored"),
+///     ],
+/// );
+///
+/// assert_eq!(config.get::<i64>("server.port"), Some(9000));
+/// assert_eq!(config.get::<Vec<String>>("server.hosts").map(|h| h.len()), Some(2));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvOverlay {
+    prefix: String,
+    separator: String,
+    list_delimiter: Option<String>,
+}
+
+impl EnvOverlay {
+    /// Create an overlay for variables starting with `prefix`.
+    ///
+    /// The prefix is matched followed by an underscore, so `"APP"` matches
+    /// `APP_PORT` but not `APPLE`.
+    #[must_use]
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: format!("{}_", prefix.trim_end_matches('_')),
+            separator: "__".to_string(),
+            list_delimiter: None,
+        }
+    }
+
+    /// Set the separator used to express nesting (default: `__`).
+    #[must_use]
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Split values containing `delimiter` into arrays.
+    ///
+    /// Splitting follows [`env::get_list`](crate::env::get_list): items are
+    /// trimmed and empty items are dropped. Without a delimiter only TOML
+    /// inline arrays (`[1, 2]`) become arrays.
+    #[must_use]
+    pub fn list_delimiter(mut self, delimiter: &str) -> Self {
+        self.list_delimiter = Some(delimiter.to_string());
+        self
+    }
+
+    /// Map an environment variable name to its dotted configuration key.
+    ///
+    /// Returns `None` if the variable does not carry the prefix or maps to
+    /// an empty key segment.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::EnvOverlay;
+    ///
+    /// let overlay = EnvOverlay::new("APP");
+    /// assert_eq!(
+    ///     overlay.key_for("APP_DATABASE__MAX_CONNECTIONS"),
+    ///     Some("database.max_connections".to_string())
+    /// );
+    /// assert_eq!(overlay.key_for("HOME"), None);
+    /// ```
+    #[must_use]
+    pub fn key_for(&self, var: &str) -> Option<String> {
+        let rest = var.strip_prefix(&self.prefix)?;
+        let parts: Vec<String> = rest
+            .split(self.separator.as_str())
+            .map(str::to_lowercase)
+            .collect();
+        if parts.iter().any(String::is_empty) {
+            return None;
+        }
+        Some(parts.join("."))
+    }
+
+    /// Build a configuration from the current process environment.
+    ///
+    /// Variables whose name or value is not valid UTF-8 are skipped.
+    #[must_use]
+    pub fn load(&self) -> Config {
+        self.load_from(
+            std::env::vars_os().filter_map(|(var, value)| {
+                Some((var.into_string().ok()?, value.into_string().ok()?))
+            }),
+        )
+    }
+
+    /// Build a configuration from an explicit set of variables.
+    #[must_use]
+    pub fn load_from<I, K, V>(&self, vars: I) -> Config
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut values = toml::map::Map::new();
+        let mut names = BTreeMap::new();
+        for (var, value) in vars {
+            if let Some(key) = self.key_for(var.as_ref()) {
+                insert_path(&mut values, &key, self.coerce(value.as_ref()));
+                names.insert(key, var.as_ref().to_string());
+            }
+        }
+        Config::from_value(toml::Value::Table(values), |key| Source::Env {
+            var: names.get(key).cloned().unwrap_or_default(),
+        })
+    }
+
+    /// Override `config` with values from the current process environment.
+    pub fn apply(&self, config: &mut Config) {
+        config.merge(&self.load(), ArrayMerge::Replace);
+    }
+
+    /// Override `config` with values from an explicit set of variables.
+    pub fn apply_from<I, K, V>(&self, config: &mut Config, vars: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        config.merge(&self.load_from(vars), ArrayMerge::Replace);
+    }
+
+    /// Coerce a raw variable value into a TOML value.
+    fn coerce(&self, raw: &str) -> toml::Value {
+        match &self.list_delimiter {
+            Some(delimiter) if raw.contains(delimiter.as_str()) => toml::Value::Array(
+                parse_list(raw, delimiter)
+                    .iter()
+                    .map(|item| infer_value(item))
+                    .collect(),
+            ),
+            _ => infer_value(raw),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_mapping() {
+        let overlay = EnvOverlay::new("APP_");
+        assert_eq!(overlay.key_for("APP_PORT"), Some("port".into()));
+        assert_eq!(
+            overlay.key_for("APP_SERVER__PORT"),
+            Some("server.port".into())
+        );
+        assert_eq!(overlay.key_for("APPLE"), None);
+        assert_eq!(overlay.key_for("APP_SERVER__"), None);
+
+        let dotted = EnvOverlay::new("APP").separator("_");
+        assert_eq!(
+            dotted.key_for("APP_SERVER_PORT"),
+            Some("server.port".into())
+        );
+    }
+
+    #[test]
+    fn test_overlay_overrides_and_coerces() {
+        let mut config = Config::new(
+            r#"
+            debug = false
+            [server]
+            host = "localhost"
+            port = 8080
+        "#,
+        );
+        EnvOverlay::new("APP").apply_from(
+            &mut config,
+            [
+                ("APP_DEBUG", "true"),
+                ("APP_SERVER__PORT", "9000"),
+                ("APP_SERVER__RATIO", "0.25"),
+                ("APP_SERVER__TAGS", "[\"a\", \"b\"]"),
+            ],
+        );
+
+        assert_eq!(config.get::<bool>("debug"), Some(true));
+        assert_eq!(
+            config.get::<String>("server.host"),
+            Some("localhost".into())
+        );
+        assert_eq!(config.get::<i64>("server.port"), Some(9000));
+        assert_eq!(config.get::<f64>("server.ratio"), Some(0.25));
+        assert_eq!(
+            config.get::<Vec<String>>("server.tags"),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_list_delimiter() {
+        let overlay = EnvOverlay::new("APP").list_delimiter(",");
+        let config = overlay.load_from([("APP_PORTS", "80, 443"), ("APP_NAME", "svc")]);
+        assert_eq!(config.get::<Vec<i64>>("ports"), Some(vec![80, 443]));
+        assert_eq!(config.get::<String>("name"), Some("svc".into()));
+
+        let plain = EnvOverlay::new("APP").load_from([("APP_PORTS", "80,443")]);
+        assert_eq!(plain.get::<String>("ports"), Some("80,443".into()));
+    }
+}
@@ -0,0 +1,505 @@
+//! Configuration file formats.
+//!
+//! TOML is always available. JSON, YAML and INI support are enabled with the
+//! `json`, `yaml` and `ini` features respectively. Every format is
+//! normalised into a TOML value tree so that [`Config`](super::Config)
+//! lookups behave identically across formats.
+
+#[cfg(any(feature = "json", feature = "yaml", feature = "ini"))]
+use super::ParseError;
+use super::{ConfigError, parse_toml};
+use std::fmt;
+use std::path::Path;
+
+/// Supported configuration file formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ConfigFormat {
+    /// TOML (always available).
+    #[default]
+    Toml,
+    /// JSON (requires the `json` feature).
+    Json,
+    /// YAML (requires the `yaml` feature).
+    Yaml,
+    /// INI (requires the `ini` feature).
+    Ini,
+}
+
+impl ConfigFormat {
+    /// Detect a format from a file extension (without the leading dot).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::ConfigFormat;
+    ///
+    /// assert_eq!(ConfigFormat::from_extension("yml"), Some(ConfigFormat::Yaml));
+    /// assert_eq!(ConfigFormat::from_extension("txt"), None);
+    /// ```
+    #[must_use]
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "ini" => Some(Self::Ini),
+            _ => None,
+        }
+    }
+
+    /// Detect a format from a file path's extension.
+    #[must_use]
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    /// Lowercase name of the format.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Ini => "ini",
+        }
+    }
+
+    /// Check whether support for this format is compiled in.
+    #[must_use]
+    pub const fn is_enabled(self) -> bool {
+        match self {
+            Self::Toml => true,
+            Self::Json => cfg!(feature = "json"),
+            Self::Yaml => cfg!(feature = "yaml"),
+            Self::Ini => cfg!(feature = "ini"),
+        }
+    }
+
+    /// Parse content in this format into a TOML value tree.
+    pub(super) fn parse(self, content: &str) -> Result<toml::Value, ConfigError> {
+        let parsed = match self {
+            Self::Toml => parse_toml(content),
+            #[cfg(feature = "json")]
+            Self::Json => parse_json(content),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => parse_yaml(content),
+            #[cfg(feature = "ini")]
+            Self::Ini => parse_ini(content),
+            #[allow(unreachable_patterns)]
+            _ => {
+                return Err(ConfigError::UnsupportedFormat(format!(
+                    "{self} (enable the `{self}` feature)"
+                )));
+            }
+        };
+        parsed.map_err(|e| ConfigError::ParseAt(Box::new(e)))
+    }
+
+    /// Render a value tree as content in this format.
+    ///
+    /// Comments and key order of any original content are not retained.
+    pub(super) fn render(self, value: &toml::Value) -> String {
+        match self {
+            #[cfg(feature = "json")]
+            Self::Json => {
+                let mut out =
+                    serde_json::to_string_pretty(&toml_to_json(value)).unwrap_or_default();
+                out.push('\n');
+                out
+            }
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml::to_string(&toml_to_yaml(value)).unwrap_or_default(),
+            #[cfg(feature = "ini")]
+            Self::Ini => render_ini(value),
+            // Content is only ever held in an enabled format, so TOML is
+            // the fallback for formats compiled out.
+            #[allow(unreachable_patterns)]
+            _ => toml::to_string_pretty(value).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parse a JSON document into a TOML value tree.
+#[cfg(feature = "json")]
+fn parse_json(content: &str) -> Result<toml::Value, ParseError> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|e| {
+        ParseError::new(e.to_string()).at_line_column(content, e.line(), e.column())
+    })?;
+    match json_to_toml(value, "")? {
+        Some(table @ toml::Value::Table(_)) => Ok(table),
+        _ => Err(ParseError::new("top-level JSON value must be an object")),
+    }
+}
+
+/// Convert a JSON value found at `key`, dropping `null`s which TOML cannot
+/// represent.
+///
+/// Integers above `i64::MAX` are refused rather than rounded to floats.
+#[cfg(feature = "json")]
+fn json_to_toml(value: serde_json::Value, key: &str) -> Result<Option<toml::Value>, ParseError> {
+    use serde_json::Value;
+
+    Ok(Some(match value {
+        Value::Null => return Ok(None),
+        Value::Bool(b) => toml::Value::Boolean(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None if n.is_u64() => return Err(integer_out_of_range(key)),
+            None => return Ok(n.as_f64().map(toml::Value::Float)),
+        },
+        Value::String(s) => toml::Value::String(s),
+        Value::Array(items) => toml::Value::Array(
+            items
+                .into_iter()
+                .filter_map(|item| json_to_toml(item, key).transpose())
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => toml::Value::Table(
+            map.into_iter()
+                .filter_map(|(k, v)| {
+                    let value = json_to_toml(v, &child_key(key, &k)).transpose()?;
+                    Some(value.map(|v| (k, v)))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+    }))
+}
+
+/// Convert a TOML value into JSON, rendering datetimes as strings.
+#[cfg(feature = "json")]
+pub(super) fn toml_to_json(value: &toml::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        toml::Value::String(s) => Value::String(s.clone()),
+        toml::Value::Integer(i) => Value::from(*i),
+        toml::Value::Float(f) => Value::from(*f),
+        toml::Value::Boolean(b) => Value::Bool(*b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .iter()
+                .map(|(k, v)| (k.clone(), toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Parse a YAML document into a TOML value tree.
+#[cfg(feature = "yaml")]
+fn parse_yaml(content: &str) -> Result<toml::Value, ParseError> {
+    let value: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| {
+        let error = ParseError::new(e.to_string());
+        match e.location() {
+            Some(location) => error.at_offset(content, location.index()),
+            None => error,
+        }
+    })?;
+    match yaml_to_toml(value, "")? {
+        Some(table @ toml::Value::Table(_)) => Ok(table),
+        // An empty document is an empty configuration.
+        None => Ok(super::empty_table()),
+        _ => Err(ParseError::new("top-level YAML value must be a mapping")),
+    }
+}
+
+/// Convert a YAML value found at `key`, dropping `null`s which TOML cannot
+/// represent.
+///
+/// Integers above `i64::MAX` are refused rather than rounded to floats.
+#[cfg(feature = "yaml")]
+fn yaml_to_toml(value: serde_yaml::Value, key: &str) -> Result<Option<toml::Value>, ParseError> {
+    use serde_yaml::Value;
+
+    Ok(Some(match value {
+        Value::Null => return Ok(None),
+        Value::Bool(b) => toml::Value::Boolean(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None if n.is_u64() => return Err(integer_out_of_range(key)),
+            None => return Ok(n.as_f64().map(toml::Value::Float)),
+        },
+        Value::String(s) => toml::Value::String(s),
+        Value::Sequence(items) => toml::Value::Array(
+            items
+                .into_iter()
+                .filter_map(|item| yaml_to_toml(item, key).transpose())
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Mapping(map) => toml::Value::Table(
+            map.into_iter()
+                .filter_map(|(k, v)| {
+                    let name = match k {
+                        Value::String(s) => s,
+                        Value::Bool(b) => b.to_string(),
+                        Value::Number(n) => n.to_string(),
+                        _ => return None,
+                    };
+                    let value = yaml_to_toml(v, &child_key(key, &name)).transpose()?;
+                    Some(value.map(|v| (name, v)))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Tagged(tagged) => return yaml_to_toml(tagged.value, key),
+    }))
+}
+
+/// Dotted key of `name` within the table at `parent`.
+#[cfg(any(feature = "json", feature = "yaml"))]
+fn child_key(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{parent}.{name}")
+    }
+}
+
+/// Error for an integer too large for TOML, whose integers are `i64`.
+#[cfg(any(feature = "json", feature = "yaml"))]
+fn integer_out_of_range(key: &str) -> ParseError {
+    ParseError::new(format!(
+        "integer at `{key}` is larger than {}, the largest supported",
+        i64::MAX
+    ))
+}
+
+/// Convert a TOML value into YAML, rendering datetimes as strings.
+#[cfg(feature = "yaml")]
+fn toml_to_yaml(value: &toml::Value) -> serde_yaml::Value {
+    use serde_yaml::Value;
+
+    match value {
+        toml::Value::String(s) => Value::String(s.clone()),
+        toml::Value::Integer(i) => Value::from(*i),
+        toml::Value::Float(f) => Value::from(*f),
+        toml::Value::Boolean(b) => Value::Bool(*b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Sequence(items.iter().map(toml_to_yaml).collect()),
+        toml::Value::Table(table) => Value::Mapping(
+            table
+                .iter()
+                .map(|(k, v)| (Value::String(k.clone()), toml_to_yaml(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Parse an INI document into a TOML value tree.
+///
+/// Properties outside any section become top-level keys, and section names
+/// are treated as dotted paths (`[server.tls]` nests under `server`).
+/// Values are typed with the same inference as environment overlays.
+#[cfg(feature = "ini")]
+fn parse_ini(content: &str) -> Result<toml::Value, ParseError> {
+    let ini = ini::Ini::load_from_str(content)
+        .map_err(|e| ParseError::new(e.msg.to_string()).at_line_column(content, e.line, e.col))?;
+
+    let mut root = toml::map::Map::new();
+    for (section, properties) in &ini {
+        for (key, value) in properties {
+            let path = section.map_or_else(|| key.to_string(), |s| format!("{s}.{key}"));
+            super::insert_path(&mut root, &path, super::infer_value(value));
+        }
+    }
+    Ok(toml::Value::Table(root))
+}
+
+/// Render a value tree as INI.
+///
+/// Top-level values come first, then one section per table named by its
+/// dotted path. Arrays are written as TOML inline arrays, which
+/// [`parse_ini`] reads back.
+#[cfg(feature = "ini")]
+fn render_ini(value: &toml::Value) -> String {
+    use std::fmt::Write as _;
+
+    fn section(out: &mut String, name: &str, table: &toml::map::Map<String, toml::Value>) {
+        let values: Vec<_> = table.iter().filter(|(_, v)| !v.is_table()).collect();
+        if !values.is_empty() {
+            if !name.is_empty() {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                let _ = writeln!(out, "[{name}]");
+            }
+            for (key, value) in values {
+                match value {
+                    toml::Value::String(s) => {
+                        let _ = writeln!(out, "{key} = {s}");
+                    }
+                    other => {
+                        let _ = writeln!(out, "{key} = {other}");
+                    }
+                }
+            }
+        }
+        for (key, child) in table {
+            if let toml::Value::Table(child) = child {
+                let path = if name.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{name}.{key}")
+                };
+                section(out, &path, child);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    if let toml::Value::Table(table) = value {
+        section(&mut out, "", table);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(
+            ConfigFormat::from_path("app/config.TOML"),
+            Some(ConfigFormat::Toml)
+        );
+        assert_eq!(
+            ConfigFormat::from_path("config.json"),
+            Some(ConfigFormat::Json)
+        );
+        assert_eq!(
+            ConfigFormat::from_path("settings.ini"),
+            Some(ConfigFormat::Ini)
+        );
+        assert_eq!(ConfigFormat::from_path("Makefile"), None);
+        assert_eq!(ConfigFormat::Yaml.to_string(), "yaml");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_config() {
+        let config = Config::try_new_with_format(
+            r#"{"name": "app", "server": {"port": 8080, "ratio": 0.5, "tls": null},
+                "hosts": ["a", "b"]}"#,
+            ConfigFormat::Json,
+        )
+        .unwrap();
+        assert_eq!(config.format(), ConfigFormat::Json);
+        assert_eq!(config.get::<String>("name"), Some("app".into()));
+        assert_eq!(config.get::<i64>("server.port"), Some(8080));
+        assert_eq!(config.get::<f64>("server.ratio"), Some(0.5));
+        assert!(!config.has_key("server.tls"));
+        assert_eq!(
+            config.get::<Vec<String>>("hosts"),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+
+        let err = Config::try_new_with_format("{\n  \"a\": ,\n}", ConfigFormat::Json).unwrap_err();
+        let ConfigError::ParseAt(details) = err else {
+            panic!("expected parse error");
+        };
+        assert_eq!(details.line(), Some(2));
+        assert!(Config::try_new_with_format("[1, 2]", ConfigFormat::Json).is_err());
+
+        let err = Config::try_new_with_format(
+            r#"{"server": {"id": 18446744073709551615}}"#,
+            ConfigFormat::Json,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ConfigError::ParseAt(_)));
+        assert!(err.to_string().contains("integer at `server.id` is larger"));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml_config() {
+        let config = Config::try_new_with_format(
+            "name: app\nserver:\n  port: 8080\n  debug: true\nhosts:\n  - a\n  - b\n",
+            ConfigFormat::Yaml,
+        )
+        .unwrap();
+        assert_eq!(config.get::<String>("name"), Some("app".into()));
+        assert_eq!(config.get::<i64>("server.port"), Some(8080));
+        assert_eq!(config.get::<bool>("server.debug"), Some(true));
+        assert_eq!(config.get::<Vec<String>>("hosts").map(|h| h.len()), Some(2));
+        assert!(Config::try_new_with_format("", ConfigFormat::Yaml).is_ok());
+        assert!(Config::try_new_with_format("a: [1, 2", ConfigFormat::Yaml).is_err());
+        assert!(
+            Config::try_new_with_format("ids:\n  - 18446744073709551615\n", ConfigFormat::Yaml)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "ini")]
+    #[test]
+    fn test_ini_config() {
+        let config = Config::try_new_with_format(
+            "name = app\n\n[server]\nport = 8080\ndebug = on\n\n[server.tls]\nenabled = false\n",
+            ConfigFormat::Ini,
+        )
+        .unwrap();
+        assert_eq!(config.get::<String>("name"), Some("app".into()));
+        assert_eq!(config.get::<i64>("server.port"), Some(8080));
+        assert_eq!(config.get::<bool>("server.debug"), Some(true));
+        assert_eq!(config.get::<bool>("server.tls.enabled"), Some(false));
+    }
+
+    #[cfg(all(feature = "json", feature = "yaml"))]
+    #[test]
+    fn test_files_parse_identically() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Server {
+            host: String,
+            port: u16,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct AppConfig {
+            server: Server,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let sources = [
+            ("app.toml", "[server]\nhost = \"localhost\"\nport = 8080\n"),
+            (
+                "app.json",
+                r#"{"server": {"host": "localhost", "port": 8080}}"#,
+            ),
+            ("app.yml", "server:\n  host: localhost\n  port: 8080\n"),
+        ];
+        for (name, content) in sources {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            let config = Config::from_file_strict(&path).unwrap();
+            assert!(config.has_key("server.port"), "{name}");
+            let parsed: AppConfig = config.parse().unwrap();
+            assert_eq!(
+                parsed.server,
+                Server {
+                    host: "localhost".into(),
+                    port: 8080
+                },
+                "{name}"
+            );
+        }
+    }
+
+    #[cfg(not(feature = "json"))]
+    #[test]
+    fn test_disabled_format() {
+        assert!(matches!(
+            Config::try_new_with_format("{}", ConfigFormat::Json),
+            Err(ConfigError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,166 @@
+//! `include` directives in configuration files.
+//!
+//! A file may list other files to load beneath it:
+//!
+//! ```toml
+//! include = ["base.toml", "~/.config/app/local.toml"]
+//! ```
+//!
+//! Paths are relative to the including file. Included files are merged in
+//! order and the including file is applied last, so it overrides them.
+
+use super::{ArrayMerge, Config, ConfigError};
+use std::path::{Path, PathBuf};
+
+/// Name of the directive key.
+const INCLUDE_KEY: &str = "include";
+
+/// Load the files `config` includes and merge it over them.
+///
+/// `chain` holds the files currently being loaded and is used to report
+/// include cycles. Every included file is recorded in `files` before it is
+/// read.
+pub(super) fn resolve(
+    mut config: Config,
+    path: &Path,
+    strict: bool,
+    chain: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<Config, ConfigError> {
+    let Some(directive) = config.remove(INCLUDE_KEY) else {
+        return Ok(config);
+    };
+    let targets = match directive {
+        toml::Value::String(include) => vec![include],
+        toml::Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                toml::Value::String(include) => Ok(include),
+                other => Err(invalid(path, &other)),
+            })
+            .collect::<Result<_, _>>()?,
+        other => return Err(invalid(path, &other)),
+    };
+
+    chain.push(canonical(path));
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut merged: Option<Config> = None;
+    for target in targets {
+        let include_path = dir.join(crate::fs::resolve_path(&target));
+        let key = canonical(&include_path);
+        if chain.contains(&key) {
+            let cycle: Vec<String> = chain
+                .iter()
+                .chain(std::iter::once(&key))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(ConfigError::Include(format!(
+                "cycle detected: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        if !files.contains(&include_path) {
+            files.push(include_path.clone());
+        }
+        let included = Config::read_file(&include_path, None, strict).map_err(|e| match e {
+            ConfigError::FileRead(msg) => {
+                ConfigError::Include(format!("{} (included from {})", msg, path.display()))
+            }
+            other => other,
+        })?;
+        let included = resolve(included, &include_path, strict, chain, files)?;
+        match &mut merged {
+            Some(base) => base.merge(&included, ArrayMerge::Replace),
+            None => merged = Some(included),
+        }
+    }
+    chain.pop();
+
+    Ok(match merged {
+        Some(mut base) => {
+            base.merge(&config, ArrayMerge::Replace);
+            base
+        }
+        None => config,
+    })
+}
+
+/// Error for an `include` value that is not a path or list of paths.
+fn invalid(path: &Path, value: &toml::Value) -> ConfigError {
+    ConfigError::Include(format!(
+        "{}: `{INCLUDE_KEY}` must be a string or array of strings, found {}",
+        path.display(),
+        value.type_str()
+    ))
+}
+
+/// Canonical form of a path for cycle detection, falling back to the path
+/// itself if it cannot be resolved.
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Source;
+
+    #[test]
+    fn test_includes_are_overridden() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("conf.d")).unwrap();
+        std::fs::write(
+            dir.path().join("conf.d/base.toml"),
+            "name = \"base\"\n[server]\nhost = \"localhost\"\nport = 80\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("conf.d/tls.toml"),
+            "include = \"base.toml\"\n[server]\nport = 443\n",
+        )
+        .unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, "include = [\"conf.d/tls.toml\"]\nname = \"app\"\n").unwrap();
+
+        let config = Config::from_file_strict(&path).unwrap();
+        assert_eq!(config.get::<String>("name"), Some("app".into()));
+        assert_eq!(
+            config.get::<String>("server.host"),
+            Some("localhost".into())
+        );
+        assert_eq!(config.get::<i64>("server.port"), Some(443));
+        assert!(!config.has_key("include"));
+        assert_eq!(
+            config.source("server.port"),
+            Some(&Source::File {
+                path: dir.path().join("conf.d").join("tls.toml"),
+                line: Some(3)
+            })
+        );
+    }
+
+    #[test]
+    fn test_include_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.toml");
+        std::fs::write(&a, "include = \"b.toml\"\n").unwrap();
+        std::fs::write(dir.path().join("b.toml"), "include = [\"a.toml\"]\n").unwrap();
+        let err = Config::from_file(&a).unwrap_err();
+        assert!(matches!(&err, ConfigError::Include(msg) if msg.starts_with("cycle detected")));
+
+        let missing = dir.path().join("missing.toml");
+        std::fs::write(&missing, "include = \"nope.toml\"\n").unwrap();
+        assert!(matches!(
+            Config::from_file(&missing),
+            Err(ConfigError::Include(_))
+        ));
+
+        let invalid = dir.path().join("invalid.toml");
+        std::fs::write(&invalid, "include = 1\n").unwrap();
+        assert!(matches!(
+            Config::from_file(&invalid),
+            Err(ConfigError::Include(_))
+        ));
+    }
+}
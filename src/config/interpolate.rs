@@ -0,0 +1,385 @@
+//! Variable interpolation in configuration strings.
+//!
+//! String values may reference other keys with `${server.host}` and
+//! environment variables with `${env:HOME}`. Either form accepts a fallback
+//! after `:-`, as in `${env:PORT:-8080}`. A literal `${` is written `$${`.
+//!
+//! References are only resolved on request, by calling
+//! [`Config::interpolated`], so files holding a literal `${` load as
+//! written. Resolving a loaded file after its includes and profile are
+//! applied lets a profile override a value other keys refer to, and
+//! [`save`](Config::save) still writes the references back as written.
+
+use super::edit::set_in_document;
+use super::{Config, ConfigError, flatten};
+use std::collections::BTreeMap;
+
+impl Config {
+    /// Resolve `${...}` references in every string value.
+    ///
+    /// The configuration as written is kept for
+    /// [`save`](Config::save). Resolving twice would treat escaped `$${`
+    /// sequences as references.
+    ///
+    /// A string consisting of a single reference takes the referenced
+    /// value with its type, so `port = "${defaults.port}"` stays an
+    /// integer. Environment variables are read from the process
+    /// environment; see [`interpolated_with`](Config::interpolated_with)
+    /// to supply them explicitly.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Interpolation`] if a reference is malformed,
+    /// names a missing key or unset variable without a fallback, or is
+    /// part of a cycle.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let config = Config::new(r#"
+    ///     base = "/srv/app"
+    ///     data = "${base}/data"
+    ///     cache = "${env:APP_CACHE_DIR_UNSET:-${base}/cache}"
+    ///     [server]
+    ///     port = 8080
+    ///     url = "http://localhost:${server.port}"
+    /// "#)
+    /// .interpolated()
+    /// .unwrap();
+    ///
+    /// assert_eq!(config.get::<String>("data"), Some("/srv/app/data".into()));
+    /// assert_eq!(config.get::<String>("server.url"), Some("http://localhost:8080".into()));
+    /// ```
+    pub fn interpolated(self) -> Result<Self, ConfigError> {
+        self.interpolated_with(|var| std::env::var(var).ok())
+    }
+
+    /// Resolve `${...}` references, looking up environment variables with
+    /// `env`.
+    ///
+    /// # Errors
+    ///
+    /// See [`interpolated`](Config::interpolated).
+    pub fn interpolated_with<F>(mut self, env: F) -> Result<Self, ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut resolver = Resolver {
+            root: &self.parsed,
+            env,
+            resolved: BTreeMap::new(),
+            stack: Vec::new(),
+        };
+        let mut changed = Vec::new();
+        for (key, value) in flatten(&self.parsed) {
+            let new_value = resolver.resolve_key(&key)?.unwrap_or_else(|| value.clone());
+            if new_value != *value {
+                changed.push((key, new_value));
+            }
+        }
+
+        if changed.is_empty() {
+            return Ok(self);
+        }
+        if let toml::Value::Table(root) = &mut self.parsed {
+            for (key, value) in &changed {
+                super::insert_path(root, key, value.clone());
+            }
+        }
+        if self.document.is_none() {
+            self.document = Some((self.content.clone(), self.format));
+        }
+        self.sync_content(|doc| {
+            for (key, value) in &changed {
+                set_in_document(doc, key, value);
+            }
+        });
+        Ok(self)
+    }
+}
+
+/// Resolves references against the original value tree, memoising each
+/// key and tracking the chain of keys being resolved to detect cycles.
+struct Resolver<'a, F> {
+    root: &'a toml::Value,
+    env: F,
+    resolved: BTreeMap<String, toml::Value>,
+    stack: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Resolver<'_, F> {
+    /// Resolved value of a key, or `None` if it does not exist.
+    fn resolve_key(&mut self, key: &str) -> Result<Option<toml::Value>, ConfigError> {
+        if let Some(value) = self.resolved.get(key) {
+            return Ok(Some(value.clone()));
+        }
+        if self.stack.iter().any(|k| k == key) {
+            let mut chain = self.stack.clone();
+            chain.push(key.to_string());
+            return Err(ConfigError::Interpolation(format!(
+                "cycle detected: {}",
+                chain.join(" -> ")
+            )));
+        }
+        let Some(raw) = lookup(self.root, key) else {
+            return Ok(None);
+        };
+
+        self.stack.push(key.to_string());
+        let value = self.resolve_value(raw);
+        self.stack.pop();
+        let value = value?;
+        self.resolved.insert(key.to_string(), value.clone());
+        Ok(Some(value))
+    }
+
+    fn resolve_value(&mut self, value: &toml::Value) -> Result<toml::Value, ConfigError> {
+        Ok(match value {
+            toml::Value::String(s) => self.resolve_string(s, 0)?,
+            toml::Value::Array(items) => toml::Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.resolve_value(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            toml::Value::Table(table) => toml::Value::Table(
+                table
+                    .iter()
+                    .map(|(k, v)| Ok((k.clone(), self.resolve_value(v)?)))
+                    .collect::<Result<_, ConfigError>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+
+    /// Resolve the references in `s`, which starts at byte `at` of the
+    /// value being resolved.
+    fn resolve_string(&mut self, s: &str, at: usize) -> Result<toml::Value, ConfigError> {
+        // A lone reference keeps the type of the referenced value.
+        if let Some(expr) = s.strip_prefix("${")
+            && let Some(end) = closing_brace(expr)
+            && end + 1 == expr.len()
+        {
+            return self.resolve_expr(&expr[..end], at);
+        }
+
+        let mut out = String::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('$') {
+            out.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            let offset = at + s.len() - tail.len();
+            if let Some(after) = tail.strip_prefix("$${") {
+                out.push_str("${");
+                rest = after;
+            } else if let Some(expr) = tail.strip_prefix("${") {
+                let end = closing_brace(expr)
+                    .ok_or_else(|| self.error("unterminated reference", offset))?;
+                match self.resolve_expr(&expr[..end], offset)? {
+                    toml::Value::String(value) => out.push_str(&value),
+                    value @ (toml::Value::Table(_) | toml::Value::Array(_)) => {
+                        return Err(self.error(
+                            &format!("cannot embed {} `{}`", value.type_str(), &expr[..end]),
+                            offset,
+                        ));
+                    }
+                    value => out.push_str(&value.to_string()),
+                }
+                rest = &expr[end + 1..];
+            } else {
+                out.push('$');
+                rest = &tail[1..];
+            }
+        }
+        out.push_str(rest);
+        Ok(toml::Value::String(out))
+    }
+
+    /// Resolve the inside of a `${...}` reference found at byte `at`.
+    fn resolve_expr(&mut self, expr: &str, at: usize) -> Result<toml::Value, ConfigError> {
+        let (name, fallback) = match expr.split_once(":-") {
+            Some((name, fallback)) => (name, Some(fallback)),
+            None => (expr, None),
+        };
+
+        let value = if let Some(var) = name.strip_prefix("env:") {
+            (self.env)(var)
+                .filter(|value| !value.is_empty())
+                .map(toml::Value::String)
+        } else {
+            self.resolve_key(name)?
+        };
+
+        match (value, fallback) {
+            (Some(value), _) => Ok(value),
+            (None, Some(fallback)) => self.resolve_string(fallback, at + name.len() + 4),
+            (None, None) if name.starts_with("env:") => Err(self.error(
+                &format!("environment variable `{}` is not set", &name[4..]),
+                at,
+            )),
+            (None, None) => Err(self.error(&format!("referenced key `{name}` does not exist"), at)),
+        }
+    }
+
+    /// An error located at byte `at` of the value being resolved. The
+    /// value itself is left out, as it may be a secret.
+    fn error(&self, message: &str, at: usize) -> ConfigError {
+        let key = self.stack.last().map_or("", String::as_str);
+        ConfigError::Interpolation(format!("{message} in `{key}` at byte {at}"))
+    }
+}
+
+/// Byte index of the `}` closing a reference, allowing nested references
+/// in fallbacks.
+fn closing_brace(expr: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in expr.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Look up a dotted key in a value tree.
+fn lookup<'a>(root: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.').try_fold(root, |value, part| value.get(part))
+}
+
+#[cfg(test)]
+// `${env:...}` in test content is interpolation syntax, not a format string.
+#[allow(clippy::literal_string_with_formatting_args)]
+mod tests {
+    use super::*;
+
+    fn env(var: &str) -> Option<String> {
+        match var {
+            "HOST" => Some("db.internal".into()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_key_and_env_references() {
+        #[derive(serde::Deserialize)]
+        struct Db {
+            copy: u16,
+        }
+        #[derive(serde::Deserialize)]
+        struct App {
+            db: Db,
+        }
+
+        let config = Config::new(
+            r#"
+            # Paths
+            root = "/srv"
+            data = "${root}/data"   # data dir
+            logs = ["${data}/app.log", "${root}/audit.log"]
+            price = "$${not_a_ref} costs $5"
+            [db]
+            host = "${env:HOST}"
+            port = 5432
+            url = "postgres://${db.host}:${db.port}/${env:DB_NAME:-app}"
+            fallback = "${env:EMPTY:-${root}}"
+            copy = "${db.port}"
+        "#,
+        )
+        .interpolated_with(env)
+        .unwrap();
+
+        assert_eq!(config.get::<String>("data"), Some("/srv/data".into()));
+        assert_eq!(
+            config.get::<Vec<String>>("logs"),
+            Some(vec!["/srv/data/app.log".into(), "/srv/audit.log".into()])
+        );
+        assert_eq!(
+            config.get::<String>("price"),
+            Some("${not_a_ref} costs $5".into())
+        );
+        assert_eq!(
+            config.get::<String>("db.url"),
+            Some("postgres://db.internal:5432/app".into())
+        );
+        assert_eq!(config.get::<String>("db.fallback"), Some("/srv".into()));
+        assert_eq!(config.get::<i64>("db.copy"), Some(5432));
+
+        // The raw content is updated in place, keeping comments.
+        assert!(config.raw().contains("data = \"/srv/data\"   # data dir"));
+        assert_eq!(config.parse::<App>().unwrap().db.copy, 5432);
+    }
+
+    #[test]
+    fn test_files_are_interpolated_on_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        let document = format!(
+            "root = \"/srv\"\ndata = \"${{root}}/data\"\n\n[profile.{}]\nroot = \"/opt\"\n",
+            crate::env::get_environment()
+        );
+        std::fs::write(&path, &document).unwrap();
+
+        let config = Config::from_file_strict(&path).unwrap();
+        assert_eq!(config.get::<String>("data"), Some("${root}/data".into()));
+
+        let mut config = config.interpolated_with(env).unwrap();
+        assert_eq!(config.get::<String>("data"), Some("/opt/data".into()));
+
+        config.set("name", "app");
+        config.save(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("data = \"${root}/data\""));
+        assert!(saved.contains("name = \"app\""));
+
+        // A literal `${` does not stop a file from loading.
+        std::fs::write(&path, "password = \"pa${ss\"\n").unwrap();
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.get::<String>("password"), Some("pa${ss".into()));
+    }
+
+    #[test]
+    fn test_interpolation_errors() {
+        let cycle = Config::new("a = \"${b}\"\nb = \"x${c}\"\nc = \"${a}\"\n")
+            .interpolated_with(env)
+            .unwrap_err();
+        assert_eq!(
+            cycle.to_string(),
+            "Config interpolation failed: cycle detected: a -> b -> c -> a"
+        );
+
+        for (content, message) in [
+            (
+                "a = \"${missing}\"",
+                "referenced key `missing` does not exist in `a` at byte 0",
+            ),
+            (
+                "a = \"${env:UNSET}\"",
+                "environment variable `UNSET` is not set in `a` at byte 0",
+            ),
+            (
+                "a = \"x${env:UNSET:-${nope}}\"",
+                "referenced key `nope` does not exist in `a` at byte 14",
+            ),
+            (
+                "[db]\npassword = \"hunter2${oops\"",
+                "unterminated reference in `db.password` at byte 7",
+            ),
+            (
+                "a = \"list: ${b}\"\nb = [1]",
+                "cannot embed array `b` in `a` at byte 6",
+            ),
+        ] {
+            let error = Config::new(content).interpolated_with(env).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("Config interpolation failed: {message}")
+            );
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Layered configuration loading.
+//!
+//! A [`LayeredConfig`] stacks several configuration sources (defaults,
+//! system file, user file, local overrides, ...) and deep-merges them into
+//! a single [`Config`]. Later layers take precedence over earlier ones.
+
+use super::{Config, ConfigError, ConfigFormat, flatten};
+use std::path::Path;
+
+/// Strategy used when both the base and the overlay contain an array
+/// for the same key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// The overlay array replaces the base array entirely.
+    #[default]
+    Replace,
+    /// The overlay array elements are appended to the base array.
+    Append,
+}
+
+/// Builder that deep-merges configuration layers in precedence order.
+///
+/// Tables are merged recursively, scalar values from later layers win, and
+/// arrays are combined according to the configured [`ArrayMerge`] strategy.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::{ArrayMerge, Config, ConfigBuilder, LayeredConfig};
+///
+/// let config = LayeredConfig::new()
+///     .array_merge(ArrayMerge::Append)
+///     .with_layer(ConfigBuilder::new().set_int("port", 8080))
+///     .with_layer(Config::new(r#"
+///         port = 9000
+///         hosts = ["a"]
+///     "#))
+///     .with_layer(Config::new(r#"hosts = ["b"]"#))
+///     .build();
+///
+/// assert_eq!(config.get::<i64>("port"), Some(9000));
+/// assert_eq!(
+///     config.get::<Vec<String>>("hosts"),
+///     Some(vec!["a".to_string(), "b".to_string()])
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LayeredConfig {
+    layers: Vec<Config>,
+    arrays: ArrayMerge,
+    strict: bool,
+}
+
+impl LayeredConfig {
+    /// Create an empty set of layers.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how arrays present in several layers are combined.
+    #[must_use]
+    pub const fn array_merge(mut self, strategy: ArrayMerge) -> Self {
+        self.arrays = strategy;
+        self
+    }
+
+    /// Refuse malformed files instead of treating them as empty.
+    ///
+    /// When enabled, file layers are loaded with
+    /// [`Config::from_file_strict`].
+    #[must_use]
+    pub const fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Add a layer on top of the existing ones.
+    #[must_use]
+    pub fn with_layer(mut self, layer: impl Into<Config>) -> Self {
+        self.layers.push(layer.into());
+        self
+    }
+
+    /// Add a file layer on top of the existing ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, or if it is malformed
+    /// and [`strict`](LayeredConfig::strict) mode is enabled.
+    pub fn with_file<P: AsRef<Path>>(self, path: P) -> Result<Self, ConfigError> {
+        let layer = if self.strict {
+            Config::from_file_strict(path)?
+        } else {
+            Config::from_file(path)?
+        };
+        Ok(self.with_layer(layer))
+    }
+
+    /// Add a file layer if the file exists, skipping it otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn with_optional_file<P: AsRef<Path>>(self, path: P) -> Result<Self, ConfigError> {
+        if path.as_ref().exists() {
+            self.with_file(path)
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Number of layers added so far.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Check whether no layers have been added.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Merge all layers into a single configuration.
+    #[must_use]
+    pub fn build(self) -> Config {
+        let mut layers = self.layers.into_iter();
+        let Some(mut merged) = layers.next() else {
+            return Config::new("");
+        };
+        for layer in layers {
+            merged.merge(&layer, self.arrays);
+        }
+        merged
+    }
+}
+
+impl Config {
+    /// Deep-merge another configuration on top of this one.
+    ///
+    /// Values from `other` take precedence. Nested tables are merged key by
+    /// key, and arrays are combined according to `arrays`. The merged
+    /// configuration is re-rendered as TOML, and that rendering is what
+    /// [`save`](Config::save) writes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{ArrayMerge, Config};
+    ///
+    /// let mut config = Config::new("[server]\nhost = \"localhost\"\nport = 80");
+    /// config.merge(&Config::new("[server]\nport = 8080"), ArrayMerge::Replace);
+    ///
+    /// assert_eq!(config.get::<String>("server.host"), Some("localhost".into()));
+    /// assert_eq!(config.get::<i64>("server.port"), Some(8080));
+    /// ```
+    pub fn merge(&mut self, other: &Self, arrays: ArrayMerge) {
+        merge_values(&mut self.parsed, other.parsed.clone(), arrays);
+        self.content = toml::to_string_pretty(&self.parsed).unwrap_or_default();
+        self.format = ConfigFormat::Toml;
+
+        // Values from `other` win; keys it replaced with tables or scalars
+        // no longer exist in the merged tree.
+        let leaves = flatten(&self.parsed);
+        let mut sources = std::mem::take(&mut self.sources);
+        sources.retain(|key, _| leaves.contains_key(key));
+        for (key, source) in &other.sources {
+            if leaves.contains_key(key) {
+                sources.insert(key.clone(), source.clone());
+            }
+        }
+        self.sources = sources;
+        self.secrets.extend(other.secrets.iter().cloned());
+        self.document = None;
+    }
+}
+
+/// Recursively merge `overlay` into `base`.
+fn merge_values(base: &mut toml::Value, overlay: toml::Value, arrays: ArrayMerge) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value, arrays),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (toml::Value::Array(base), toml::Value::Array(overlay)) if arrays == ArrayMerge::Append => {
+            base.extend(overlay);
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigBuilder;
+
+    #[test]
+    fn test_later_layers_win() {
+        let config = LayeredConfig::new()
+            .with_layer(Config::new(
+                r#"
+                name = "defaults"
+                [server]
+                host = "localhost"
+                port = 80
+            "#,
+            ))
+            .with_layer(Config::new(
+                r"
+                [server]
+                port = 8080
+            ",
+            ))
+            .build();
+
+        assert_eq!(config.get::<String>("name"), Some("defaults".into()));
+        assert_eq!(
+            config.get::<String>("server.host"),
+            Some("localhost".into())
+        );
+        assert_eq!(config.get::<i64>("server.port"), Some(8080));
+    }
+
+    #[test]
+    fn test_array_merge_strategies() {
+        let base = Config::new("hosts = [\"a\", \"b\"]");
+        let overlay = Config::new("hosts = [\"c\"]");
+
+        let replaced = LayeredConfig::new()
+            .with_layer(base.clone())
+            .with_layer(overlay.clone())
+            .build();
+        assert_eq!(
+            replaced.get::<Vec<String>>("hosts"),
+            Some(vec!["c".to_string()])
+        );
+
+        let appended = LayeredConfig::new()
+            .array_merge(ArrayMerge::Append)
+            .with_layer(base)
+            .with_layer(overlay)
+            .build();
+        assert_eq!(
+            appended.get::<Vec<String>>("hosts"),
+            Some(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_scalar_replaces_table() {
+        let config = LayeredConfig::new()
+            .with_layer(Config::new("[log]\nlevel = \"info\""))
+            .with_layer(Config::new("log = \"off\""))
+            .build();
+        assert_eq!(config.get::<String>("log"), Some("off".into()));
+        assert!(!config.has_key("log.level"));
+    }
+
+    #[test]
+    fn test_builder_and_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, "[server]\nport = 9000\n").unwrap();
+
+        let config = LayeredConfig::new()
+            .with_layer(ConfigBuilder::new().set_string("name", "app"))
+            .with_file(&path)
+            .unwrap()
+            .with_optional_file(dir.path().join("missing.toml"))
+            .unwrap()
+            .build();
+
+        assert_eq!(config.get::<String>("name"), Some("app".into()));
+        assert_eq!(config.get::<i64>("server.port"), Some(9000));
+        assert!(
+            LayeredConfig::new()
+                .with_file(dir.path().join("missing.toml"))
+                .is_err()
+        );
+
+        let broken = dir.path().join("broken.toml");
+        std::fs::write(&broken, "port = = 1\n").unwrap();
+        assert!(LayeredConfig::new().with_file(&broken).is_ok());
+        assert!(matches!(
+            LayeredConfig::new().strict(true).with_file(&broken),
+            Err(ConfigError::ParseAt(_))
+        ));
+    }
+
+    #[test]
+    fn test_merged_config_parses() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Server {
+            host: String,
+            port: u16,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct AppConfig {
+            server: Server,
+        }
+
+        let mut config = Config::new("[server]\nhost = \"localhost\"\nport = 80");
+        config.merge(&Config::new("[server]\nport = 8080"), ArrayMerge::Replace);
+        let parsed: AppConfig = config.parse().unwrap();
+        assert_eq!(parsed.server.host, "localhost");
+        assert_eq!(parsed.server.port, 8080);
+    }
+
+    #[test]
+    fn test_empty_layers() {
+        let layers = LayeredConfig::new();
+        assert!(layers.is_empty());
+        assert_eq!(layers.len(), 0);
+        assert!(!layers.build().has_key("anything"));
+    }
+}
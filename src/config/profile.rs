@@ -0,0 +1,145 @@
+//! Per-environment profile sections.
+//!
+//! A configuration may carry overrides for each deployment environment
+//! under `[profile.<name>]`:
+//!
+//! ```toml
+//! [server]
+//! port = 8080
+//!
+//! [profile.production.server]
+//! port = 80
+//! ```
+
+use super::edit::set_in_document;
+use super::{ArrayMerge, Config, Source, flatten};
+
+/// Name of the table holding profiles.
+const PROFILE_KEY: &str = "profile";
+
+impl Config {
+    /// Merge the `[profile.<name>]` table over the rest of the
+    /// configuration and drop all profile tables.
+    ///
+    /// Returns `true` if a profile named `name` was found. Otherwise the
+    /// configuration is left as it is, including any `profile` key that is
+    /// not a table of profiles. For TOML content, the comments and layout of
+    /// the rest of the document are preserved. Configurations loaded with
+    /// [`from_file`](Config::from_file) have the profile for
+    /// [`env::get_environment`](crate::env::get_environment) applied
+    /// already.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let mut config = Config::new(r#"
+    ///     [server]
+    ///     host = "localhost"
+    ///     port = 8080
+    ///
+    ///     [profile.production.server]
+    ///     host = "0.0.0.0"
+    /// "#);
+    ///
+    /// assert!(config.apply_profile("production"));
+    /// assert_eq!(config.get::<String>("server.host"), Some("0.0.0.0".into()));
+    /// assert_eq!(config.get::<i64>("server.port"), Some(8080));
+    /// assert!(!config.has_key("profile"));
+    /// ```
+    pub fn apply_profile(&mut self, name: &str) -> bool {
+        let Some(toml::Value::Table(profiles)) = self.parsed.get(PROFILE_KEY) else {
+            return false;
+        };
+        let Some(profile @ toml::Value::Table(_)) = profiles.get(name).cloned() else {
+            return false;
+        };
+
+        // Profile values keep the sources they were declared with.
+        let origins = self.sources.clone();
+        let prefix = format!("{PROFILE_KEY}.{name}.");
+        let overlay = Self::from_value(profile, |key| {
+            origins
+                .get(&format!("{prefix}{key}"))
+                .cloned()
+                .unwrap_or(Source::Runtime)
+        });
+        self.remove(PROFILE_KEY);
+
+        // Merging re-renders the content, so edit the original document
+        // instead to keep its layout.
+        let (content, format) = (std::mem::take(&mut self.content), self.format);
+        self.merge(&overlay, ArrayMerge::Replace);
+        self.content = content;
+        self.format = format;
+        self.sync_content(|doc| {
+            for (key, value) in flatten(&overlay.parsed) {
+                set_in_document(doc, &key, value);
+            }
+        });
+        self.profile = Some(name.to_string());
+        true
+    }
+
+    /// Key under which the applied profile would override `key` in the
+    /// file as written.
+    pub(super) fn profile_key(&self, key: &str) -> Option<String> {
+        let name = self.profile.as_ref()?;
+        Some(format!("{PROFILE_KEY}.{name}.{key}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::get_environment;
+
+    #[test]
+    fn test_apply_profile() {
+        let content = "[server]\nport = 8080\n\n[profile.staging.server]\nport = 8081\n";
+
+        let mut config = Config::new(content);
+        assert!(!config.apply_profile("production"));
+        assert_eq!(config.get::<i64>("server.port"), Some(8080));
+        assert!(config.has_key("profile.staging.server.port"));
+        assert_eq!(config.raw(), content);
+
+        let mut config = Config::new("profile = \"staging\"\n");
+        assert!(!config.apply_profile("staging"));
+        assert_eq!(config.get::<String>("profile"), Some("staging".into()));
+
+        let mut config = Config::new(content);
+        assert!(config.apply_profile("staging"));
+        assert_eq!(config.get::<i64>("server.port"), Some(8081));
+        assert_eq!(
+            config.source("server.port"),
+            Some(&Source::Inline { line: Some(5) })
+        );
+        assert!(!config.has_key("profile"));
+
+        let mut config = Config::new(
+            "# Server\n[server]\nport = 8080 # default\n\n[profile.staging.server]\nport = 8081\n",
+        );
+        assert!(config.apply_profile("staging"));
+        assert_eq!(config.raw(), "# Server\n[server]\nport = 8081 # default\n");
+    }
+
+    #[test]
+    fn test_from_file_applies_current_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(
+            &path,
+            format!(
+                "debug = false\n\n[profile.{}]\ndebug = true\n",
+                get_environment()
+            ),
+        )
+        .unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.get::<bool>("debug"), Some(true));
+        assert!(!config.has_key("profile"));
+    }
+}
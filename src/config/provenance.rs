@@ -0,0 +1,317 @@
+//! Provenance of configuration values.
+//!
+//! Every leaf value in a [`Config`] records the [`Source`] that set it, so
+//! the effective value of a key can be traced back to a file and line, a
+//! builder call or an environment variable.
+
+use super::secret::REDACTED;
+use super::{Config, flatten};
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::path::PathBuf;
+use toml_edit::{ImDocument, TableLike};
+
+/// Origin of a configuration value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Source {
+    /// Loaded from a file.
+    File {
+        /// Path of the file.
+        path: PathBuf,
+        /// Line the key is defined on, when known.
+        line: Option<usize>,
+    },
+    /// Parsed from in-memory content, such as [`Config::new`].
+    Inline {
+        /// Line the key is defined on, when known.
+        line: Option<usize>,
+    },
+    /// Set through a [`ConfigBuilder`](super::ConfigBuilder).
+    Builder,
+    /// Default value declared in a [`Schema`](super::Schema).
+    Default,
+    /// Set from an environment variable.
+    Env {
+        /// Name of the variable.
+        var: String,
+    },
+    /// Set from a command-line argument.
+    Arg {
+        /// The override flag and the key it set, such as
+        /// `--set server.port`. The value is left out so that secrets do
+        /// not leak into explanations.
+        arg: String,
+    },
+    /// Set at runtime with [`Config::set`].
+    Runtime,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File {
+                path,
+                line: Some(line),
+            } => write!(f, "file {}:{line}", path.display()),
+            Self::File { path, line: None } => write!(f, "file {}", path.display()),
+            Self::Inline { line: Some(line) } => write!(f, "inline content, line {line}"),
+            Self::Inline { line: None } => f.write_str("inline content"),
+            Self::Builder => f.write_str("builder"),
+            Self::Default => f.write_str("schema default"),
+            Self::Env { var } => write!(f, "environment variable {var}"),
+            Self::Arg { arg } => write!(f, "command-line argument {arg}"),
+            Self::Runtime => f.write_str("runtime override"),
+        }
+    }
+}
+
+impl Config {
+    /// Source that set the value at a dotted key.
+    ///
+    /// Only leaf values (including arrays and empty tables) have a source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{Config, Source};
+    ///
+    /// let config = Config::new("[server]\nport = 8080\n");
+    /// assert_eq!(config.source("server.port"), Some(&Source::Inline { line: Some(2) }));
+    /// assert_eq!(config.source("server"), None);
+    /// ```
+    #[must_use]
+    pub fn source(&self, key: &str) -> Option<&Source> {
+        self.sources.get(key)
+    }
+
+    /// Describe the effective value of a key and where it came from.
+    ///
+    /// Secret values are redacted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{Config, EnvOverlay};
+    ///
+    /// let mut config = Config::new("[server]\nport = 8080\n");
+    /// EnvOverlay::new("APP").apply_from(&mut config, [("APP_SERVER__PORT", "9000")]);
+    ///
+    /// assert_eq!(
+    ///     config.explain("server.port").unwrap(),
+    ///     "server.port = 9000 (from environment variable APP_SERVER__PORT)"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn explain(&self, key: &str) -> Option<String> {
+        let source = self.source(key)?;
+        let value = self.get::<toml::Value>(key)?;
+        let value = self.display_value(key, &value);
+        Some(format!("{key} = {value} (from {source})"))
+    }
+
+    /// Every effective leaf value with its source, sorted by key.
+    ///
+    /// Values are returned as-is, including secrets.
+    #[must_use]
+    pub fn sources(&self) -> Vec<(String, &toml::Value, &Source)> {
+        flatten(&self.parsed)
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let source = self.sources.get(&key)?;
+                Some((key, value, source))
+            })
+            .collect()
+    }
+
+    /// Render every effective value with its source, one per line.
+    ///
+    /// Secret values are redacted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let config = Config::new("name = \"app\"\nport = 8080\n");
+    /// assert_eq!(
+    ///     config.explain_all(),
+    ///     "name = \"app\" (from inline content, line 1)\n\
+    ///      port = 8080 (from inline content, line 2)\n"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn explain_all(&self) -> String {
+        let mut out = String::new();
+        for (key, value, source) in self.sources() {
+            let value = self.display_value(&key, value);
+            let _ = writeln!(out, "{key} = {value} (from {source})");
+        }
+        out
+    }
+
+    /// A value rendered for display, redacted if the key is secret.
+    fn display_value(&self, key: &str, value: &toml::Value) -> String {
+        if self.is_secret(key) {
+            format!("\"{REDACTED}\"")
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// Record `source` for every leaf at or below `key`, dropping the
+    /// sources of values it replaced.
+    pub(super) fn record_source(&mut self, key: &str, source: &Source) {
+        self.forget_sources(key);
+        // Ancestors are tables now, so they are no longer leaves.
+        for (end, _) in key.match_indices('.') {
+            self.sources.remove(&key[..end]);
+        }
+        if let Some(value) = self.get::<toml::Value>(key) {
+            for leaf in leaf_keys(key, &value) {
+                self.sources.insert(leaf, source.clone());
+            }
+        }
+    }
+
+    /// Drop the sources of `key` and everything below it.
+    pub(super) fn forget_sources(&mut self, key: &str) {
+        let nested = format!("{key}.");
+        self.sources
+            .retain(|existing, _| existing != key && !existing.starts_with(&nested));
+    }
+}
+
+/// Assign a source to every leaf of a value tree.
+pub(super) fn sources_for(
+    parsed: &toml::Value,
+    source: impl Fn(&str) -> Source,
+) -> BTreeMap<String, Source> {
+    flatten(parsed)
+        .into_keys()
+        .map(|key| {
+            let source = source(&key);
+            (key, source)
+        })
+        .collect()
+}
+
+/// Dotted keys of the leaves at or below `key`.
+fn leaf_keys(key: &str, value: &toml::Value) -> Vec<String> {
+    let nested = flatten(value);
+    if nested.is_empty() {
+        vec![key.to_string()]
+    } else {
+        nested.into_keys().map(|k| format!("{key}.{k}")).collect()
+    }
+}
+
+/// Line on which each leaf key of a TOML document is defined.
+pub(super) fn toml_lines(content: &str) -> BTreeMap<String, usize> {
+    fn walk(content: &str, prefix: &str, table: &dyn TableLike, out: &mut BTreeMap<String, usize>) {
+        for (key, item) in table.iter() {
+            let path = if prefix.is_empty() {
+                key.to_string()
+            } else {
+                format!("{prefix}.{key}")
+            };
+            match item.as_table_like() {
+                Some(child) if !child.is_empty() => walk(content, &path, child, out),
+                _ => {
+                    let span = table
+                        .key(key)
+                        .and_then(toml_edit::Key::span)
+                        .or_else(|| item.span());
+                    if let Some(span) = span {
+                        out.insert(path, line_of(content, span.start));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    if let Ok(doc) = ImDocument::parse(content) {
+        walk(content, "", doc.as_table(), &mut out);
+    }
+    out
+}
+
+/// One-based line number of a byte offset.
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigBuilder, EnvOverlay, LayeredConfig};
+
+    #[test]
+    fn test_toml_lines() {
+        let lines = toml_lines(
+            "name = \"app\"\n\n[server]\nport = 8080\ntls = { enabled = true }\n\n[[users]]\nname = \"a\"\n",
+        );
+        assert_eq!(lines.get("name"), Some(&1));
+        assert_eq!(lines.get("server.port"), Some(&4));
+        assert_eq!(lines.get("server.tls.enabled"), Some(&5));
+        assert_eq!(lines.get("users"), Some(&7));
+    }
+
+    #[test]
+    fn test_sources_follow_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, "[server]\nhost = \"0.0.0.0\"\n").unwrap();
+
+        let mut config = LayeredConfig::new()
+            .with_layer(
+                ConfigBuilder::new()
+                    .set_string("server.host", "localhost")
+                    .set_int("server.port", 80)
+                    .set_bool("debug", false),
+            )
+            .with_file(&path)
+            .unwrap()
+            .build();
+        EnvOverlay::new("APP").apply_from(&mut config, [("APP_SERVER__PORT", "8080")]);
+        config.set("debug", true);
+
+        assert_eq!(
+            config.source("server.host"),
+            Some(&Source::File {
+                path: path.clone(),
+                line: Some(2)
+            })
+        );
+        assert_eq!(
+            config.source("server.port"),
+            Some(&Source::Env {
+                var: "APP_SERVER__PORT".into()
+            })
+        );
+        assert_eq!(config.source("debug"), Some(&Source::Runtime));
+
+        let dump = config.explain_all();
+        assert_eq!(dump.lines().count(), 3);
+        assert!(dump.contains(&format!(
+            "server.host = \"0.0.0.0\" (from file {}:2)",
+            path.display()
+        )));
+    }
+
+    #[test]
+    fn test_sources_track_edits() {
+        let mut config = Config::new("[server]\nport = 8080\nhost = \"localhost\"\n");
+        config.set("server", toml::map::Map::new());
+        assert_eq!(config.source("server.port"), None);
+        assert_eq!(config.source("server"), Some(&Source::Runtime));
+
+        config.set("server.port", 9000);
+        assert_eq!(config.source("server"), None);
+        assert_eq!(config.source("server.port"), Some(&Source::Runtime));
+
+        config.remove("server");
+        assert!(config.sources().is_empty());
+        assert_eq!(config.explain("server.port"), None);
+    }
+}
@@ -0,0 +1,432 @@
+//! Hot-reloading configuration files.
+//!
+//! A [`ReloadableConfig`] owns the configuration loaded from a file and
+//! re-reads it when the file or any file it includes changes, either on
+//! demand or from a background polling thread started with
+//! [`ReloadableConfig::watch`].
+
+use super::{Config, ConfigDiff, ConfigError};
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Callback invoked with the new configuration and the changes from the
+/// previous one.
+type Subscriber = Box<dyn Fn(&Config, &ConfigDiff) + Send + Sync>;
+
+/// Hash of a file's contents, used to detect modifications that keep its
+/// modification time or size.
+type Signature = Option<u64>;
+
+/// A configuration that can be reloaded from its file while in use.
+///
+/// Readers call [`current`](ReloadableConfig::current) to get a snapshot of
+/// the latest good configuration. When the file changes and fails to parse,
+/// the previous configuration is kept and the error is recorded in
+/// [`last_error`](ReloadableConfig::last_error).
+///
+/// Handles are cheap to clone and share the same underlying state.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use commons::config::ReloadableConfig;
+/// use std::time::Duration;
+///
+/// let config = ReloadableConfig::open("config.toml").unwrap();
+/// config.subscribe(|_, diff| print!("{diff}"));
+///
+/// let _watcher = config.watch(Duration::from_secs(2));
+/// let port: Option<i64> = config.current().get("server.port");
+/// ```
+#[derive(Clone)]
+pub struct ReloadableConfig {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    path: PathBuf,
+    current: RwLock<Arc<Config>>,
+    /// Files the configuration was loaded from, with their signatures.
+    /// Locked for the whole of a reload so reloads never interleave.
+    files: Mutex<Vec<(PathBuf, Signature)>>,
+    last_error: Mutex<Option<String>>,
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl ReloadableConfig {
+    /// Load the file and start tracking it for changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is malformed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref().to_path_buf();
+        let known = [(path.clone(), file_signature(&path))];
+        let mut paths = Vec::new();
+        let config = Config::load_file(&path, None, true, &mut paths)?;
+        Ok(Self {
+            shared: Arc::new(Shared {
+                path,
+                current: RwLock::new(Arc::new(config)),
+                files: Mutex::new(signatures(paths, &known)),
+                last_error: Mutex::new(None),
+                subscribers: Mutex::new(Vec::new()),
+            }),
+        })
+    }
+
+    /// Path of the watched file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.shared.path
+    }
+
+    /// Snapshot of the latest successfully loaded configuration.
+    #[must_use]
+    pub fn current(&self) -> Arc<Config> {
+        Arc::clone(
+            &self
+                .shared
+                .current
+                .read()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    /// Error from the most recent failed reload, if any.
+    ///
+    /// Cleared by the next successful reload.
+    #[must_use]
+    pub fn last_error(&self) -> Option<String> {
+        self.shared
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Register a callback run after every reload that changes at least
+    /// one key.
+    ///
+    /// The callback receives the new configuration and a [`ConfigDiff`]
+    /// listing the keys that were added, removed or modified. Callbacks run
+    /// on the thread performing the reload and must not call `subscribe` or
+    /// reload the configuration.
+    pub fn subscribe<F>(&self, callback: F)
+    where
+        F: Fn(&Config, &ConfigDiff) + Send + Sync + 'static,
+    {
+        self.shared
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Re-read the file now, regardless of whether it changed.
+    ///
+    /// Returns the changes from the previous configuration. Subscribers
+    /// are notified when the diff is not empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is malformed; the
+    /// previous configuration stays in effect.
+    pub fn reload(&self) -> Result<ConfigDiff, ConfigError> {
+        let mut files = self
+            .shared
+            .files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.reload_files(&mut files)
+    }
+
+    /// Reload the file, replacing `files` with the files it now includes.
+    ///
+    /// Callers hold the lock on `files` for the duration of the reload.
+    fn reload_files(
+        &self,
+        files: &mut Vec<(PathBuf, Signature)>,
+    ) -> Result<ConfigDiff, ConfigError> {
+        let shared = &self.shared;
+        // Signatures taken before reading, so a write racing with the
+        // reload is picked up by the next check.
+        let known: Vec<_> = files
+            .iter()
+            .map(|(path, _)| (path.clone(), file_signature(path)))
+            .collect();
+        let mut paths = Vec::new();
+        let loaded = Config::load_file(&shared.path, None, true, &mut paths);
+        *files = signatures(paths, &known);
+
+        let config = match loaded {
+            Ok(config) => config,
+            Err(e) => {
+                *shared
+                    .last_error
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner) = Some(e.to_string());
+                return Err(e);
+            }
+        };
+        *shared
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+
+        let config = Arc::new(config);
+        let previous = std::mem::replace(
+            &mut *shared
+                .current
+                .write()
+                .unwrap_or_else(PoisonError::into_inner),
+            Arc::clone(&config),
+        );
+
+        let changed = previous.diff(&config);
+        if !changed.is_empty() {
+            for subscriber in shared
+                .subscribers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+            {
+                subscriber(&config, &changed);
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Reload the file if its contents, or those of a file it includes,
+    /// changed since the last load.
+    ///
+    /// Returns the changes, which are empty when the file was untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file changed but cannot be read or parsed.
+    pub fn check_for_changes(&self) -> Result<ConfigDiff, ConfigError> {
+        let mut files = self
+            .shared
+            .files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if files
+            .iter()
+            .all(|(path, signature)| file_signature(path) == *signature)
+        {
+            Ok(ConfigDiff::default())
+        } else {
+            self.reload_files(&mut files)
+        }
+    }
+
+    /// Poll the file for changes from a background thread.
+    ///
+    /// Polling stops when the returned [`ConfigWatcher`] is dropped.
+    /// Failed reloads are recorded in
+    /// [`last_error`](ReloadableConfig::last_error).
+    #[must_use]
+    pub fn watch(&self, interval: Duration) -> ConfigWatcher {
+        let stop = Arc::new(AtomicBool::new(false));
+        let config = self.clone();
+        let flag = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            while !flag.load(Ordering::Relaxed) {
+                thread::park_timeout(interval);
+                if flag.load(Ordering::Relaxed) {
+                    break;
+                }
+                let _ = config.check_for_changes();
+            }
+        });
+        ConfigWatcher {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl fmt::Debug for ReloadableConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReloadableConfig")
+            .field("path", &self.shared.path)
+            .field("current", &self.current())
+            .field("last_error", &self.last_error())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Handle for a background polling thread started by
+/// [`ReloadableConfig::watch`].
+///
+/// Dropping the handle stops the thread.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// Stop polling and wait for the background thread to exit.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Pair each of `paths` with its signature, reusing the one in `known` for
+/// paths that were already tracked.
+fn signatures(paths: Vec<PathBuf>, known: &[(PathBuf, Signature)]) -> Vec<(PathBuf, Signature)> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let signature = known
+                .iter()
+                .find(|(known, _)| *known == path)
+                .map_or_else(|| file_signature(&path), |(_, signature)| *signature);
+            (path, signature)
+        })
+        .collect()
+}
+
+/// Hash of a file's contents, or `None` if it cannot be read.
+fn file_signature(path: &Path) -> Signature {
+    let mut hasher = DefaultHasher::new();
+    std::fs::read(path).ok()?.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Change;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_reload_reports_changed_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, "name = \"app\"\n[server]\nport = 8080\n").unwrap();
+
+        let config = ReloadableConfig::open(&path).unwrap();
+        let notified = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&notified);
+        config.subscribe(move |new, changed| {
+            assert_eq!(new.get::<i64>("server.port"), Some(9000));
+            assert!(changed.contains("server.port"));
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert!(config.check_for_changes().unwrap().is_empty());
+
+        std::fs::write(
+            &path,
+            "name = \"app\"\n[server]\nport = 9000\nhost = \"0.0.0.0\"\n",
+        )
+        .unwrap();
+        let changed = config.check_for_changes().unwrap();
+        assert_eq!(
+            changed.keys().collect::<Vec<_>>(),
+            ["server.host", "server.port"]
+        );
+        assert_eq!(
+            changed.get("server.port"),
+            Some(&Change::Changed {
+                old: 8080.into(),
+                new: 9000.into()
+            })
+        );
+        assert_eq!(notified.load(Ordering::SeqCst), 1);
+        assert_eq!(config.current().get::<i64>("server.port"), Some(9000));
+
+        // Unchanged content does not notify.
+        assert!(config.reload().unwrap().is_empty());
+        assert_eq!(notified.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_keeps_last_good_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, "port = 8080\n").unwrap();
+
+        let config = ReloadableConfig::open(&path).unwrap();
+        std::fs::write(&path, "port = = 9000\n").unwrap();
+
+        assert!(config.reload().is_err());
+        assert!(config.last_error().is_some());
+        assert_eq!(config.current().get::<i64>("port"), Some(8080));
+
+        std::fs::write(&path, "port = 9000\n").unwrap();
+        assert!(config.reload().is_ok());
+        assert!(config.last_error().is_none());
+        assert_eq!(config.current().get::<i64>("port"), Some(9000));
+    }
+
+    #[test]
+    fn test_included_files_are_watched() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.toml");
+        let local = dir.path().join("local.toml");
+        std::fs::write(&base, "port = 8080\n").unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, "include = \"base.toml\"\n").unwrap();
+
+        let config = ReloadableConfig::open(&path).unwrap();
+        assert!(config.check_for_changes().unwrap().is_empty());
+
+        // Same size, and possibly the same modification time.
+        std::fs::write(&base, "port = 9000\n").unwrap();
+        assert!(config.check_for_changes().unwrap().contains("port"));
+        assert_eq!(config.current().get::<i64>("port"), Some(9000));
+        std::fs::write(&base, "port = 9001\n").unwrap();
+        assert!(config.check_for_changes().unwrap().contains("port"));
+
+        // A newly included file is watched even before it exists.
+        std::fs::write(&path, "include = [\"base.toml\", \"local.toml\"]\n").unwrap();
+        assert!(config.check_for_changes().is_err());
+        std::fs::write(&local, "name = \"app\"\n").unwrap();
+        assert!(config.check_for_changes().unwrap().contains("name"));
+    }
+
+    #[test]
+    fn test_watcher_picks_up_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        std::fs::write(&path, "port = 8080\n").unwrap();
+
+        let config = ReloadableConfig::open(&path).unwrap();
+        let watcher = config.watch(Duration::from_millis(10));
+        std::fs::write(&path, "port = 9000\nname = \"app\"\n").unwrap();
+
+        let mut reloaded = false;
+        for _ in 0..200 {
+            if config.current().get::<i64>("port") == Some(9000) {
+                reloaded = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        watcher.stop();
+        assert!(reloaded);
+    }
+}
@@ -0,0 +1,651 @@
+//! Declarative schema validation for configurations.
+//!
+//! A [`Schema`] lists the keys a configuration is expected to contain along
+//! with their types and constraints. Validating a [`Config`] against it
+//! reports every violation at once, keyed by dotted path, using the same
+//! `(field, ValidationError)` model as [`Validator`].
+
+use super::{Config, ConfigError, Source, flatten, infer_value, insert_path};
+use crate::validation::{ValidationError, Validator, validate_length, validate_range};
+use std::collections::BTreeMap;
+
+/// Named string check applied by [`Field::pattern`].
+pub(super) type Pattern = (String, fn(&str) -> bool);
+
+/// Expected type of a configuration value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    /// A string.
+    String,
+    /// An integer.
+    Integer,
+    /// A float.
+    Float,
+    /// An integer or a float.
+    Number,
+    /// A boolean.
+    Boolean,
+    /// A TOML date, time or datetime.
+    Datetime,
+    /// An array.
+    Array,
+    /// A table.
+    Table,
+}
+
+impl ValueType {
+    /// Lowercase name of the type, matching [`toml::Value::type_str`].
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Float => "float",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Datetime => "datetime",
+            Self::Array => "array",
+            Self::Table => "table",
+        }
+    }
+
+    /// Type of a value.
+    #[must_use]
+    pub const fn of(value: &toml::Value) -> Self {
+        match value {
+            toml::Value::String(_) => Self::String,
+            toml::Value::Integer(_) => Self::Integer,
+            toml::Value::Float(_) => Self::Float,
+            toml::Value::Boolean(_) => Self::Boolean,
+            toml::Value::Datetime(_) => Self::Datetime,
+            toml::Value::Array(_) => Self::Array,
+            toml::Value::Table(_) => Self::Table,
+        }
+    }
+
+    /// Check whether a value has this type.
+    #[must_use]
+    pub const fn matches(self, value: &toml::Value) -> bool {
+        matches!(
+            (self, value),
+            (Self::String, toml::Value::String(_))
+                | (Self::Integer | Self::Number, toml::Value::Integer(_))
+                | (Self::Float | Self::Number, toml::Value::Float(_))
+                | (Self::Boolean, toml::Value::Boolean(_))
+                | (Self::Datetime, toml::Value::Datetime(_))
+                | (Self::Array, toml::Value::Array(_))
+                | (Self::Table, toml::Value::Table(_))
+        )
+    }
+}
+
+/// Constraints for a single configuration key.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::{Field, ValueType};
+/// use commons::validation::is_valid_url;
+///
+/// let field = Field::new("server.url")
+///     .required()
+///     .of_type(ValueType::String)
+///     .pattern("url", is_valid_url);
+/// assert_eq!(field.key(), "server.url");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Field {
+    key: String,
+    required: bool,
+    value_type: Option<ValueType>,
+    pub(super) min: Option<f64>,
+    pub(super) max: Option<f64>,
+    pub(super) length: Option<(usize, usize)>,
+    pub(super) allowed: Vec<toml::Value>,
+    pub(super) patterns: Vec<Pattern>,
+    secret: bool,
+    doc: Option<String>,
+    default: Option<toml::Value>,
+    env: Option<String>,
+}
+
+impl Field {
+    /// Create an optional, unconstrained field for a dotted key.
+    #[must_use]
+    pub fn new(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            required: false,
+            value_type: None,
+            min: None,
+            max: None,
+            length: None,
+            allowed: Vec::new(),
+            patterns: Vec::new(),
+            secret: false,
+            doc: None,
+            default: None,
+            env: None,
+        }
+    }
+
+    /// Require the key to be present.
+    #[must_use]
+    pub const fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Require the value to have the given type.
+    #[must_use]
+    pub const fn of_type(mut self, value_type: ValueType) -> Self {
+        self.value_type = Some(value_type);
+        self
+    }
+
+    /// Require a numeric value to be within `min..=max`.
+    #[must_use]
+    pub const fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Require a numeric value to be at least `min`.
+    #[must_use]
+    pub const fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Require a numeric value to be at most `max`.
+    #[must_use]
+    pub const fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Require a string's length or an array's element count to be within
+    /// `min..=max`.
+    #[must_use]
+    pub const fn length(mut self, min: usize, max: usize) -> Self {
+        self.length = Some((min, max));
+        self
+    }
+
+    /// Restrict the value to a set of allowed values.
+    #[must_use]
+    pub fn one_of<V: Into<toml::Value>>(mut self, allowed: impl IntoIterator<Item = V>) -> Self {
+        self.allowed = allowed.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Require a string value to satisfy a named check, such as
+    /// [`is_valid_email`](crate::validation::is_valid_email).
+    #[must_use]
+    pub fn pattern(mut self, name: &str, check: fn(&str) -> bool) -> Self {
+        self.patterns.push((name.to_string(), check));
+        self
+    }
+
+    /// Treat the value as secret (see [`Config::mark_secrets`]).
+    #[must_use]
+    pub const fn secret(mut self) -> Self {
+        self.secret = true;
+        self
+    }
+
+    /// Describe the field, for generated example files.
+    #[must_use]
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_string());
+        self
+    }
+
+    /// Value used when the key is absent (see [`Schema::defaults`]).
+    #[must_use]
+    pub fn default_value(mut self, value: impl Into<toml::Value>) -> Self {
+        self.default = Some(value.into());
+        self
+    }
+
+    /// Environment variable that overrides the value (see
+    /// [`Schema::env_overrides`]).
+    #[must_use]
+    pub fn env(mut self, var: &str) -> Self {
+        self.env = Some(var.to_string());
+        self
+    }
+
+    /// Dotted key this field applies to.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Description of the field, if any.
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    /// Default value, if any.
+    #[must_use]
+    pub const fn default(&self) -> Option<&toml::Value> {
+        self.default.as_ref()
+    }
+
+    /// Environment variable overriding the value, if any.
+    #[must_use]
+    pub fn env_var(&self) -> Option<&str> {
+        self.env.as_deref()
+    }
+
+    /// Whether the key must be present.
+    #[must_use]
+    pub const fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Whether the value is secret.
+    #[must_use]
+    pub const fn is_secret(&self) -> bool {
+        self.secret
+    }
+
+    /// Expected type of the value, if constrained.
+    #[must_use]
+    pub const fn value_type(&self) -> Option<ValueType> {
+        self.value_type
+    }
+
+    /// Record every violation of this field's constraints.
+    #[allow(clippy::cast_precision_loss)]
+    fn check(&self, config: &Config, validator: &mut Validator) {
+        let key = self.key.as_str();
+        let Some(value) = config.get::<toml::Value>(key) else {
+            if self.required {
+                validator.check(key, || {
+                    Err(ValidationError::Custom("required key is missing".into()))
+                });
+            }
+            return;
+        };
+
+        if let Some(expected) = self.value_type
+            && !expected.matches(&value)
+        {
+            validator.check(key, || {
+                Err(ValidationError::Custom(format!(
+                    "expected {}, found {}",
+                    expected.name(),
+                    value.type_str()
+                )))
+            });
+            return;
+        }
+
+        let number = match value {
+            toml::Value::Integer(i) => Some(i as f64),
+            toml::Value::Float(f) => Some(f),
+            _ => None,
+        };
+        if let Some(number) = number
+            && (self.min.is_some() || self.max.is_some())
+        {
+            let min = self.min.unwrap_or(f64::NEG_INFINITY);
+            let max = self.max.unwrap_or(f64::INFINITY);
+            validator.check(key, || validate_range(number, min, max).map(|_| ()));
+        }
+
+        if let Some((min, max)) = self.length {
+            match &value {
+                toml::Value::String(s) => {
+                    validator.check(key, || validate_length(s, min, max).map(|_| ()));
+                }
+                toml::Value::Array(items) => {
+                    let len = items.len();
+                    validator.check(key, || {
+                        if len < min {
+                            Err(ValidationError::TooShort { min, actual: len })
+                        } else if len > max {
+                            Err(ValidationError::TooLong { max, actual: len })
+                        } else {
+                            Ok(())
+                        }
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if !self.allowed.is_empty() && !self.allowed.contains(&value) {
+            validator.check(key, || {
+                Err(ValidationError::NotInSet {
+                    allowed: self.allowed.iter().map(ToString::to_string).collect(),
+                })
+            });
+        }
+
+        if let toml::Value::String(s) = &value {
+            for (name, check) in &self.patterns {
+                validator.check(key, || {
+                    if check(s) {
+                        Ok(())
+                    } else {
+                        Err(ValidationError::InvalidPattern {
+                            pattern: name.clone(),
+                        })
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Set of field constraints a configuration must satisfy.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::{Config, Field, Schema, ValueType};
+///
+/// let schema = Schema::new()
+///     .field(Field::new("name").required().of_type(ValueType::String))
+///     .field(Field::new("server.port").of_type(ValueType::Integer).range(1.0, 65535.0))
+///     .field(Field::new("log.level").one_of(["debug", "info", "warn", "error"]));
+///
+/// let config = Config::new(r#"
+///     [server]
+///     port = 70000
+///     [log]
+///     level = "verbose"
+/// "#);
+///
+/// let errors = schema.validate(&config).unwrap_err();
+/// let keys: Vec<&str> = errors.iter().map(|(key, _)| key.as_str()).collect();
+/// assert_eq!(keys, ["name", "server.port", "log.level"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    fields: Vec<Field>,
+}
+
+impl Schema {
+    /// Create an empty schema.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Describe an existing configuration.
+    ///
+    /// Every leaf becomes a field, sorted by key, typed after its value and
+    /// with the value as its default. Secret keys are marked secret. Useful
+    /// to document the defaults set up with a
+    /// [`ConfigBuilder`](super::ConfigBuilder).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{ConfigBuilder, Schema, ValueType};
+    ///
+    /// let defaults = ConfigBuilder::new()
+    ///     .set_string("server.host", "localhost")
+    ///     .set_int("server.port", 8080)
+    ///     .build();
+    ///
+    /// let schema = Schema::infer(&defaults);
+    /// assert_eq!(schema.fields()[1].key(), "server.port");
+    /// assert_eq!(schema.fields()[1].value_type(), Some(ValueType::Integer));
+    /// assert!(schema.example_toml().contains("port = 8080"));
+    /// ```
+    #[must_use]
+    pub fn infer(config: &Config) -> Self {
+        let fields = flatten(&config.parsed)
+            .into_iter()
+            .map(|(key, value)| {
+                let field = Field::new(&key)
+                    .of_type(ValueType::of(value))
+                    .default_value(value.clone());
+                if config.is_secret(&key) {
+                    field.secret()
+                } else {
+                    field
+                }
+            })
+            .collect();
+        Self { fields }
+    }
+
+    /// Add a field to the schema.
+    #[must_use]
+    pub fn field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Fields declared in the schema, in declaration order.
+    #[must_use]
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Add the fields of another schema under a table key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{Field, Schema};
+    ///
+    /// let database = Schema::new().field(Field::new("url").required());
+    /// let schema = Schema::new().nest("database", database);
+    /// assert_eq!(schema.fields()[0].key(), "database.url");
+    /// ```
+    #[must_use]
+    pub fn nest(mut self, prefix: &str, schema: Self) -> Self {
+        self.fields
+            .extend(schema.fields.into_iter().map(|mut field| {
+                field.key = format!("{prefix}.{}", field.key);
+                field
+            }));
+        self
+    }
+
+    /// Add the fields of another schema under a table key that may be
+    /// absent, as for an `Option` of a nested struct.
+    ///
+    /// The fields are made optional and lose their defaults, so a missing
+    /// table stays missing; their types and constraints still apply when
+    /// the table is given.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{Field, Schema};
+    ///
+    /// let tls = Schema::new()
+    ///     .field(Field::new("cert").required())
+    ///     .field(Field::new("verify").default_value(true));
+    /// let schema = Schema::new().nest_optional("tls", tls);
+    /// assert!(!schema.fields()[0].is_required());
+    /// assert!(!schema.defaults().has_key("tls"));
+    /// ```
+    #[must_use]
+    pub fn nest_optional(self, prefix: &str, mut schema: Self) -> Self {
+        for field in &mut schema.fields {
+            field.required = false;
+            field.default = None;
+        }
+        self.nest(prefix, schema)
+    }
+
+    /// Configuration holding the default value of every field that has one.
+    ///
+    /// Use it as the lowest layer under files and overrides.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{ArrayMerge, Config, Field, Schema};
+    ///
+    /// let schema = Schema::new()
+    ///     .field(Field::new("server.host").default_value("localhost"))
+    ///     .field(Field::new("server.port").default_value(8080));
+    ///
+    /// let mut config = schema.defaults();
+    /// config.merge(&Config::new("[server]\nport = 9000"), ArrayMerge::Replace);
+    /// assert_eq!(config.get::<String>("server.host"), Some("localhost".into()));
+    /// assert_eq!(config.get::<i64>("server.port"), Some(9000));
+    /// ```
+    #[must_use]
+    pub fn defaults(&self) -> Config {
+        let mut values = toml::map::Map::new();
+        for field in &self.fields {
+            if let Some(value) = &field.default {
+                insert_path(&mut values, &field.key, value.clone());
+            }
+        }
+        let mut config = Config::from_value(toml::Value::Table(values), |_| Source::Default);
+        config.mark_secrets(self);
+        config
+    }
+
+    /// Configuration holding the fields whose [`env`](Field::env) variable
+    /// is set in the current process environment.
+    #[must_use]
+    pub fn env_overrides(&self) -> Config {
+        self.env_overrides_with(|var| std::env::var(var).ok())
+    }
+
+    /// Like [`env_overrides`](Schema::env_overrides), reading variables
+    /// through `env`.
+    ///
+    /// Values are coerced like those of an
+    /// [`EnvOverlay`](super::EnvOverlay).
+    #[must_use]
+    pub fn env_overrides_with(&self, env: impl Fn(&str) -> Option<String>) -> Config {
+        let mut values = toml::map::Map::new();
+        let mut names = BTreeMap::new();
+        for field in &self.fields {
+            if let Some(var) = &field.env
+                && let Some(raw) = env(var)
+            {
+                insert_path(&mut values, &field.key, infer_value(&raw));
+                names.insert(field.key.clone(), var.clone());
+            }
+        }
+        Config::from_value(toml::Value::Table(values), |key| Source::Env {
+            var: names.get(key).cloned().unwrap_or_default(),
+        })
+    }
+
+    /// Validate a configuration, collecting every violation.
+    ///
+    /// # Errors
+    ///
+    /// Returns every `(dotted key, error)` pair if any constraint fails.
+    pub fn validate(&self, config: &Config) -> Result<(), Vec<(String, ValidationError)>> {
+        let mut validator = Validator::new();
+        for field in &self.fields {
+            field.check(config, &mut validator);
+        }
+        validator.finish()
+    }
+}
+
+impl Config {
+    /// Validate this configuration against a [`Schema`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Validation`] listing every violation.
+    pub fn validate(&self, schema: &Schema) -> Result<(), ConfigError> {
+        schema.validate(self).map_err(ConfigError::Validation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::is_valid_email;
+
+    fn schema() -> Schema {
+        Schema::new()
+            .field(Field::new("name").required().of_type(ValueType::String))
+            .field(
+                Field::new("server.port")
+                    .required()
+                    .of_type(ValueType::Integer)
+                    .range(1.0, 65535.0),
+            )
+            .field(
+                Field::new("server.ratio")
+                    .of_type(ValueType::Number)
+                    .max(1.0),
+            )
+            .field(Field::new("admin").pattern("email", is_valid_email))
+            .field(Field::new("hosts").of_type(ValueType::Array).length(1, 3))
+            .field(Field::new("mode").one_of(["fast", "safe"]))
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let config = Config::new(
+            r#"
+            name = "app"
+            admin = "ops@example.com"
+            hosts = ["a"]
+            mode = "safe"
+            [server]
+            port = 8080
+            ratio = 0.5
+        "#,
+        );
+        assert!(schema().validate(&config).is_ok());
+        assert!(config.validate(&schema()).is_ok());
+    }
+
+    #[test]
+    fn test_collects_all_violations() {
+        let config = Config::new(
+            r#"
+            admin = "not-an-email"
+            hosts = []
+            mode = "yolo"
+            [server]
+            port = "8080"
+            ratio = 2
+        "#,
+        );
+        let errors = schema().validate(&config).unwrap_err();
+        let keys: Vec<&str> = errors.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "name",
+                "server.port",
+                "server.ratio",
+                "admin",
+                "hosts",
+                "mode"
+            ]
+        );
+        assert_eq!(
+            errors[1].1,
+            ValidationError::Custom("expected integer, found string".into())
+        );
+        assert!(matches!(errors[2].1, ValidationError::AboveMax { .. }));
+        assert_eq!(
+            errors[3].1,
+            ValidationError::InvalidPattern {
+                pattern: "email".into()
+            }
+        );
+        assert_eq!(errors[4].1, ValidationError::TooShort { min: 1, actual: 0 });
+
+        let err = config.validate(&schema()).unwrap_err();
+        assert!(matches!(&err, ConfigError::Validation(e) if e.len() == 6));
+        assert!(err.to_string().contains("server.port: expected integer"));
+    }
+}
@@ -0,0 +1,299 @@
+//! Secret configuration values.
+//!
+//! Keys are secret when marked with [`Config::mark_secret`], declared
+//! with [`Field::secret`](super::Field::secret) in a schema, or named like
+//! a credential (`password`, `db_password`, `api_token`, ...). Secret
+//! values are redacted from `Debug` output, [`Config::redacted`], the
+//! provenance dumps of [`Config::explain`] and the source line quoted by a
+//! [`ParseError`](super::ParseError).
+
+use super::{Config, FromTomlValue, Schema, flatten, insert_path};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+
+/// Placeholder shown instead of secret values.
+pub(super) const REDACTED: &str = "[REDACTED]";
+
+/// Deserialization error for a [`Secret`], which replaces the inner
+/// error so that messages quoting the value do not leak it.
+pub(super) const INVALID_SECRET: &str = "invalid value for a secret";
+
+/// Key names, or `_`-separated suffixes, treated as secret by default.
+const SECRET_NAMES: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "private_key",
+    "credentials",
+];
+
+/// A value that is never printed.
+///
+/// `Debug` and `Display` show `[REDACTED]`, and deserialization errors do
+/// not quote the value; use [`expose`](Secret::expose) to read it. `Secret` can be obtained
+/// from [`Config::get`] or used as a field in types deserialized with
+/// [`Config::parse`].
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::{Config, Secret};
+///
+/// let config = Config::new("[database]\npassword = \"hunter2\"");
+/// let password: Secret<String> = config.get("database.password").unwrap();
+///
+/// assert_eq!(password.expose(), "hunter2");
+/// assert_eq!(format!("{password:?}"), "[REDACTED]");
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wrap a value.
+    pub const fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Borrow the secret value.
+    pub const fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Unwrap the secret value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: FromTomlValue> FromTomlValue for Secret<T> {
+    fn from_toml_value(value: &toml::Value) -> Option<Self> {
+        T::from_toml_value(value).map(Self)
+    }
+
+    fn from_toml_value_strict(value: &toml::Value) -> Option<Self> {
+        T::from_toml_value_strict(value).map(Self)
+    }
+
+    fn expected_type() -> String {
+        T::expected_type()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer)
+            .map(Self)
+            .map_err(|_| serde::de::Error::custom(INVALID_SECRET))
+    }
+}
+
+impl Config {
+    /// Mark a dotted key, and everything below it, as secret.
+    pub fn mark_secret(&mut self, key: &str) {
+        self.secrets.insert(key.to_string());
+    }
+
+    /// Mark every field declared [`secret`](super::Field::secret) in a
+    /// schema.
+    pub fn mark_secrets(&mut self, schema: &Schema) {
+        for field in schema.fields().iter().filter(|field| field.is_secret()) {
+            self.mark_secret(field.key());
+        }
+    }
+
+    /// Check whether the value at a dotted key is secret.
+    ///
+    /// A key is secret if it or one of its parents was marked, or if any
+    /// segment is named like a credential: `password`, `token`, `secret`,
+    /// `api_key` and similar, alone or as a `_`-separated suffix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let mut config = Config::new("");
+    /// config.mark_secret("oauth");
+    ///
+    /// assert!(config.is_secret("database.password"));
+    /// assert!(config.is_secret("github_token"));
+    /// assert!(config.is_secret("oauth.client_id"));
+    /// assert!(!config.is_secret("database.host"));
+    /// ```
+    #[must_use]
+    pub fn is_secret(&self, key: &str) -> bool {
+        let marked = std::iter::once(key)
+            .chain(key.match_indices('.').map(|(end, _)| &key[..end]))
+            .any(|prefix| self.secrets.contains(prefix));
+        marked || key.split('.').any(is_secret_name)
+    }
+
+    /// Render the configuration as TOML with secret values replaced by
+    /// `[REDACTED]`.
+    ///
+    /// Use this instead of [`raw`](Config::raw) when logging.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    ///
+    /// let config = Config::new("user = \"admin\"\npassword = \"hunter2\"");
+    /// assert!(config.redacted().contains("password = \"[REDACTED]\""));
+    /// assert!(!config.redacted().contains("hunter2"));
+    /// ```
+    #[must_use]
+    pub fn redacted(&self) -> String {
+        toml::to_string_pretty(&self.redacted_value()).unwrap_or_default()
+    }
+
+    /// The value tree with secret leaves replaced by `[REDACTED]`.
+    pub(super) fn redacted_value(&self) -> toml::Value {
+        let mut value = self.parsed.clone();
+        if let toml::Value::Table(root) = &mut value {
+            for key in flatten(&self.parsed).into_keys() {
+                if self.is_secret(&key) {
+                    insert_path(root, &key, toml::Value::String(REDACTED.into()));
+                }
+            }
+        }
+        value
+    }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("format", &self.format)
+            .field("values", &self.redacted_value())
+            .field("sources", &self.sources)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Check whether a key segment names a credential.
+fn is_secret_name(segment: &str) -> bool {
+    let segment = segment.to_ascii_lowercase();
+    SECRET_NAMES.iter().any(|name| {
+        segment == *name
+            || segment
+                .strip_suffix(name)
+                .is_some_and(|rest| rest.ends_with('_') || rest.ends_with('-'))
+    })
+}
+
+/// Key assigned on a line of source content, for `key = value`,
+/// `key: value` and `- key: value` lines in any supported format.
+fn line_key(line: &str) -> Option<&str> {
+    let (key, _) = line.split_once(['=', ':'])?;
+    Some(key.trim().trim_start_matches('-').trim())
+}
+
+/// Whether a line of source content assigns a value to a key named like a
+/// credential.
+pub(super) fn defines_secret(line: &str) -> bool {
+    line_key(line).is_some_and(|key| {
+        key.split('.')
+            .any(|segment| is_secret_name(segment.trim().trim_matches(['"', '\''])))
+    })
+}
+
+/// A line of source content with the assigned value replaced by
+/// `[REDACTED]`.
+pub(super) fn redact_line(line: &str) -> String {
+    line.find(['=', ':']).map_or_else(
+        || REDACTED.to_string(),
+        |end| format!("{} {REDACTED}", &line[..=end]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Field;
+
+    const CONTENT: &str = r#"
+        api_token = "tok-123"
+        [database]
+        host = "db.internal"
+        password = "hunter2"
+        [oauth]
+        client_id = "id-456"
+    "#;
+
+    #[test]
+    fn test_secret_names() {
+        assert!(is_secret_name("password"));
+        assert!(is_secret_name("DB_PASSWORD"));
+        assert!(is_secret_name("stripe-api_key"));
+        assert!(!is_secret_name("tokens_per_second"));
+        assert!(!is_secret_name("passwordless"));
+    }
+
+    #[test]
+    fn test_redaction() {
+        let mut config = Config::new(CONTENT);
+        config.mark_secrets(&Schema::new().field(Field::new("oauth.client_id").secret()));
+
+        for output in [
+            format!("{config:?}"),
+            config.redacted(),
+            config.explain_all(),
+        ] {
+            for secret in ["tok-123", "hunter2", "id-456"] {
+                assert!(!output.contains(secret), "{secret} leaked in {output}");
+            }
+            assert!(output.contains("db.internal"));
+        }
+        assert_eq!(
+            config.explain("database.password").unwrap(),
+            "database.password = \"[REDACTED]\" (from inline content, line 5)"
+        );
+
+        // Values stay readable through the API.
+        assert_eq!(
+            config.get::<String>("database.password"),
+            Some("hunter2".into())
+        );
+    }
+
+    #[test]
+    fn test_secret_wrapper() {
+        #[derive(Debug, Deserialize)]
+        struct Database {
+            host: String,
+            password: Secret<String>,
+        }
+
+        let config = Config::new(CONTENT);
+        let database: Database = config.parse_at("database").unwrap();
+        assert_eq!(database.password.expose(), "hunter2");
+        assert!(!format!("{database:?}").contains("hunter2"));
+        assert_eq!(database.host, "db.internal");
+
+        let token = config.require::<Secret<String>>("api_token").unwrap();
+        assert_eq!(token.to_string(), REDACTED);
+        assert_eq!(token.into_inner(), "tok-123");
+    }
+}
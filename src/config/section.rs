@@ -0,0 +1,173 @@
+//! Scoped views of configuration tables.
+
+use super::{Config, ConfigError, Source, flatten};
+use serde::de::DeserializeOwned;
+
+impl Config {
+    /// Extract the table at a dotted key as a configuration of its own.
+    ///
+    /// Keys in the returned configuration are relative to the table, and
+    /// its values keep their [`Source`]s and secrecy.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::MissingKey`] if the key is absent, or
+    /// [`ConfigError::TypeMismatch`] if it is not a table.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Database {
+    ///     url: String,
+    ///     pool: u32,
+    /// }
+    ///
+    /// let config = Config::new(r#"
+    ///     [database]
+    ///     url = "postgres://localhost"
+    ///     pool = 10
+    /// "#);
+    ///
+    /// let database = config.section("database").unwrap();
+    /// assert_eq!(database.get::<u32>("pool"), Some(10));
+    /// let parsed: Database = database.parse().unwrap();
+    /// assert_eq!(parsed.url, "postgres://localhost");
+    /// ```
+    pub fn section(&self, key: &str) -> Result<Self, ConfigError> {
+        let value = self.require::<toml::Value>(key)?;
+        if !value.is_table() {
+            return Err(ConfigError::TypeMismatch {
+                key: key.to_string(),
+                expected: "table".into(),
+                actual: value.type_str(),
+            });
+        }
+
+        let prefix = format!("{key}.");
+        let mut section = Self::from_value(value, |relative| {
+            self.sources
+                .get(&format!("{prefix}{relative}"))
+                .cloned()
+                .unwrap_or(Source::Runtime)
+        });
+        section.secrets = flatten(&section.parsed)
+            .into_keys()
+            .filter(|leaf| self.is_secret(&format!("{prefix}{leaf}")))
+            .collect();
+        Ok(section)
+    }
+
+    /// Deserialize the value at a dotted key into a typed value.
+    ///
+    /// Unlike [`parse`](Config::parse), only the selected table or array
+    /// is converted, straight from the parsed value tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::MissingKey`] if the key is absent, or
+    /// [`ConfigError::Parse`] if the value cannot be deserialized into `T`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::Config;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// let config = Config::new(r#"
+    ///     [[auth.users]]
+    ///     name = "alice"
+    ///     [[auth.users]]
+    ///     name = "bob"
+    /// "#);
+    ///
+    /// let users: Vec<User> = config.parse_at("auth.users").unwrap();
+    /// assert_eq!(users[1].name, "bob");
+    /// ```
+    pub fn parse_at<T: DeserializeOwned>(&self, key: &str) -> Result<T, ConfigError> {
+        let value = self.require::<toml::Value>(key)?;
+        value.try_into().map_err(|e: toml::de::Error| {
+            ConfigError::Parse(format!("{key}: {}", e.message().trim()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Pool {
+        min: u32,
+        max: u32,
+    }
+
+    const CONTENT: &str = r#"
+        [database]
+        url = "postgres://localhost"
+        [database.pool]
+        min = 1
+        max = 10
+        [[database.replicas]]
+        url = "postgres://replica"
+    "#;
+
+    #[test]
+    fn test_section() {
+        let config = Config::new(CONTENT);
+        let database = config.section("database").unwrap();
+
+        assert_eq!(database.get::<u32>("pool.max"), Some(10));
+        assert!(!database.has_key("database"));
+        assert_eq!(
+            database.source("pool.max"),
+            Some(&Source::Inline { line: Some(6) })
+        );
+        assert_eq!(
+            database.section("pool").unwrap().parse::<Pool>().unwrap(),
+            Pool { min: 1, max: 10 }
+        );
+
+        assert!(matches!(
+            config.section("cache"),
+            Err(ConfigError::MissingKey(_))
+        ));
+        assert!(matches!(
+            config.section("database.url"),
+            Err(ConfigError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_at() {
+        let config = Config::new(CONTENT);
+        assert_eq!(
+            config.parse_at::<Pool>("database.pool").unwrap(),
+            Pool { min: 1, max: 10 }
+        );
+        assert_eq!(config.parse_at::<u32>("database.pool.min").unwrap(), 1);
+        assert_eq!(
+            config
+                .parse_at::<Vec<toml::Value>>("database.replicas")
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let err = config.parse_at::<Pool>("database").unwrap_err();
+        assert!(err.to_string().contains("database: missing field `min`"));
+        assert!(matches!(
+            config.parse_at::<Pool>("missing"),
+            Err(ConfigError::MissingKey(_))
+        ));
+    }
+}
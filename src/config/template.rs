@@ -0,0 +1,442 @@
+//! Documented configuration templates and JSON Schema output.
+
+use super::{Config, ConfigBuilder, Field, Schema, Source, ValueType};
+use std::fmt::Write as _;
+
+impl Schema {
+    /// Render a commented example TOML file for the schema.
+    ///
+    /// Every field is preceded by its description and constraints. Fields
+    /// with a default value are written out; the others are commented out
+    /// with a placeholder. Secret fields never show their default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{Field, Schema, ValueType};
+    ///
+    /// let schema = Schema::new()
+    ///     .field(Field::new("name").required().of_type(ValueType::String).doc("Service name."))
+    ///     .field(
+    ///         Field::new("server.port")
+    ///             .of_type(ValueType::Integer)
+    ///             .range(1.0, 65535.0)
+    ///             .default_value(8080)
+    ///             .env("APP_PORT"),
+    ///     );
+    ///
+    /// let example = schema.example_toml();
+    /// assert!(example.starts_with("# Service name.\n"));
+    /// assert!(example.contains("[server]\n"));
+    /// ```
+    ///
+    /// produces:
+    ///
+    /// ```toml
+    /// # Service name.
+    /// # Type: string. Required.
+    /// # name = ""
+    ///
+    /// [server]
+    /// # Type: integer. Range: 1 to 65535. Environment variable: APP_PORT.
+    /// port = 8080
+    /// ```
+    #[must_use]
+    pub fn example_toml(&self) -> String {
+        // Group fields by table, keeping the order tables first appear in.
+        let mut tables: Vec<(&str, Vec<&Field>)> = Vec::new();
+        for field in self.fields() {
+            let table = field.key().rsplit_once('.').map_or("", |(table, _)| table);
+            match tables.iter_mut().find(|(name, _)| *name == table) {
+                Some((_, fields)) => fields.push(field),
+                None => tables.push((table, vec![field])),
+            }
+        }
+        tables.sort_by_key(|(name, _)| !name.is_empty());
+
+        let mut out = String::new();
+        for (table, fields) in tables {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            if !table.is_empty() {
+                let header: Vec<String> = table.split('.').map(toml_key).collect();
+                let _ = writeln!(out, "[{}]", header.join("."));
+            }
+            for (index, field) in fields.into_iter().enumerate() {
+                if index > 0 && field.description().is_some() {
+                    out.push('\n');
+                }
+                write_field(&mut out, field);
+            }
+        }
+        out
+    }
+
+    /// Render the schema as a JSON Schema (draft 2020-12) document.
+    ///
+    /// Dotted keys become nested objects. Descriptions, defaults, bounds
+    /// and allowed values are carried over; pattern checks named after a
+    /// JSON Schema format (`email`, `url`, `ipv4`, ...) set `format`.
+    /// Secret defaults are left out.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::{Field, Schema, ValueType};
+    ///
+    /// let schema = Schema::new().field(
+    ///     Field::new("server.port")
+    ///         .required()
+    ///         .of_type(ValueType::Integer)
+    ///         .range(1.0, 65535.0),
+    /// );
+    ///
+    /// let json = schema.json_schema();
+    /// assert_eq!(json["required"][0], "server");
+    /// assert_eq!(json["properties"]["server"]["properties"]["port"]["maximum"], 65535.0);
+    /// ```
+    #[cfg(feature = "json")]
+    #[must_use]
+    pub fn json_schema(&self) -> serde_json::Value {
+        use serde_json::{Map, Value, json};
+
+        let mut root = json!({ "type": "object", "properties": {} });
+        for field in self.fields() {
+            let segments: Vec<&str> = field.key().split('.').collect();
+            let Some((name, parents)) = segments.split_last() else {
+                continue;
+            };
+
+            let mut object = &mut root;
+            for parent in parents {
+                if field.is_required() {
+                    require(object, parent);
+                }
+                let properties = &mut object["properties"];
+                if !properties[*parent].is_object() {
+                    properties[*parent] = json!({ "type": "object", "properties": {} });
+                }
+                object = &mut properties[*parent];
+            }
+            if field.is_required() {
+                require(object, name);
+            }
+            object["properties"][*name] = Value::Object(json_property(field));
+        }
+
+        let Value::Object(mut document) = root else {
+            return Value::Null;
+        };
+        let mut out = Map::new();
+        out.insert(
+            "$schema".into(),
+            "https://json-schema.org/draft/2020-12/schema".into(),
+        );
+        out.append(&mut document);
+        Value::Object(out)
+    }
+}
+
+impl ConfigBuilder {
+    /// Schema describing the values set so far, for generating templates.
+    ///
+    /// See [`Schema::infer`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::config::ConfigBuilder;
+    ///
+    /// let builder = ConfigBuilder::new()
+    ///     .set_string("log.level", "info")
+    ///     .set_bool("debug", false);
+    ///
+    /// let example = builder.schema().example_toml();
+    /// assert!(example.starts_with("# Type: boolean.\ndebug = false\n"));
+    /// ```
+    #[must_use]
+    pub fn schema(&self) -> Schema {
+        let config =
+            Config::from_value(toml::Value::Table(self.values.clone()), |_| Source::Builder);
+        Schema::infer(&config)
+    }
+}
+
+/// Add a property name to an object's `required` list once.
+#[cfg(feature = "json")]
+fn require(object: &mut serde_json::Value, name: &str) {
+    let required = &mut object["required"];
+    if !required.is_array() {
+        *required = serde_json::Value::Array(Vec::new());
+    }
+    if let Some(names) = required.as_array_mut()
+        && !names.iter().any(|existing| existing == name)
+    {
+        names.push(name.into());
+    }
+}
+
+/// JSON Schema keywords describing a single field.
+#[cfg(feature = "json")]
+fn json_property(field: &Field) -> serde_json::Map<String, serde_json::Value> {
+    use super::format::toml_to_json;
+    use serde_json::Value;
+
+    let mut property = serde_json::Map::new();
+    let mut insert = |keyword: &str, value: Value| {
+        property.insert(keyword.to_string(), value);
+    };
+
+    match field.value_type() {
+        Some(ValueType::Datetime) => {
+            insert("type", "string".into());
+            insert("format", "date-time".into());
+        }
+        Some(ValueType::Float | ValueType::Number) => insert("type", "number".into()),
+        Some(ValueType::Table) => insert("type", "object".into()),
+        Some(other) => insert("type", other.name().into()),
+        None => {}
+    }
+    if let Some(doc) = field.description() {
+        insert("description", doc.into());
+    }
+    if let Some(default) = field.default()
+        && !field.is_secret()
+    {
+        insert("default", toml_to_json(default));
+    }
+    if let Some(min) = field.min {
+        insert("minimum", min.into());
+    }
+    if let Some(max) = field.max {
+        insert("maximum", max.into());
+    }
+    if let Some((min, max)) = field.length {
+        let (min_key, max_key) = if field.value_type() == Some(ValueType::Array) {
+            ("minItems", "maxItems")
+        } else {
+            ("minLength", "maxLength")
+        };
+        insert(min_key, min.into());
+        insert(max_key, max.into());
+    }
+    if !field.allowed.is_empty() {
+        insert(
+            "enum",
+            Value::Array(field.allowed.iter().map(toml_to_json).collect()),
+        );
+    }
+    for (name, _) in &field.patterns {
+        let format = match name.as_str() {
+            "email" => "email",
+            "url" | "uri" => "uri",
+            "ipv4" => "ipv4",
+            "ipv6" => "ipv6",
+            "uuid" => "uuid",
+            _ => continue,
+        };
+        insert("format", format.into());
+    }
+    if field.is_secret() {
+        insert("writeOnly", true.into());
+    }
+    property
+}
+
+/// Write a field's comments and its value or placeholder.
+fn write_field(out: &mut String, field: &Field) {
+    if let Some(doc) = field.description() {
+        for line in doc.lines() {
+            let _ = writeln!(out, "# {line}");
+        }
+    }
+    let notes = notes(field);
+    if !notes.is_empty() {
+        let _ = writeln!(out, "# {}", notes.join(" "));
+    }
+
+    let name = toml_key(field.key().rsplit('.').next().unwrap_or_default());
+    match field.default() {
+        Some(value) if !field.is_secret() => {
+            let _ = writeln!(out, "{name} = {value}");
+        }
+        _ => {
+            let _ = writeln!(out, "# {name} = {}", placeholder(field.value_type()));
+        }
+    }
+}
+
+/// One sentence per constraint of a field.
+fn notes(field: &Field) -> Vec<String> {
+    let mut notes = Vec::new();
+    if let Some(value_type) = field.value_type() {
+        notes.push(format!("Type: {}.", value_type.name()));
+    }
+    if field.is_required() {
+        notes.push("Required.".into());
+    }
+    match (field.min, field.max) {
+        (Some(min), Some(max)) => notes.push(format!("Range: {min} to {max}.")),
+        (Some(min), None) => notes.push(format!("Minimum: {min}.")),
+        (None, Some(max)) => notes.push(format!("Maximum: {max}.")),
+        (None, None) => {}
+    }
+    if let Some((min, max)) = field.length {
+        notes.push(format!("Length: {min} to {max}."));
+    }
+    if !field.allowed.is_empty() {
+        let allowed: Vec<String> = field.allowed.iter().map(ToString::to_string).collect();
+        notes.push(format!("One of: {}.", allowed.join(", ")));
+    }
+    for (name, _) in &field.patterns {
+        notes.push(format!("Format: {name}."));
+    }
+    if field.is_secret() {
+        notes.push("Secret.".into());
+    }
+    if let Some(var) = field.env_var() {
+        notes.push(format!("Environment variable: {var}."));
+    }
+    notes
+}
+
+/// Example value for a field without a default.
+const fn placeholder(value_type: Option<ValueType>) -> &'static str {
+    match value_type {
+        Some(ValueType::Integer) => "0",
+        Some(ValueType::Float | ValueType::Number) => "0.0",
+        Some(ValueType::Boolean) => "false",
+        Some(ValueType::Datetime) => "1970-01-01T00:00:00Z",
+        Some(ValueType::Array) => "[]",
+        Some(ValueType::Table) => "{}",
+        Some(ValueType::String) | None => "\"\"",
+    }
+}
+
+/// Quote a key segment unless it is a valid bare key.
+fn toml_key(segment: &str) -> String {
+    let bare = !segment.is_empty()
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'));
+    if bare {
+        segment.to_string()
+    } else {
+        toml::Value::String(segment.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example_toml_round_trips() {
+        let schema = Schema::new()
+            .field(
+                Field::new("server.host")
+                    .doc("Bind address.\nUse 0.0.0.0 for all.")
+                    .default_value("127.0.0.1"),
+            )
+            .field(Field::new("server.port").default_value(8080))
+            .field(
+                Field::new("log.level")
+                    .one_of(["info", "debug"])
+                    .default_value("info"),
+            )
+            .field(Field::new("db.password").secret().default_value("changeme"))
+            .field(Field::new("debug").of_type(ValueType::Boolean))
+            .field(Field::new("labels.my key").default_value(vec!["a"]));
+
+        let example = schema.example_toml();
+        assert!(example.starts_with("# Type: boolean.\n# debug = false\n\n[server]\n"));
+        assert!(
+            example.contains("# Bind address.\n# Use 0.0.0.0 for all.\nhost = \"127.0.0.1\"\n")
+        );
+        assert!(example.contains("# One of: \"info\", \"debug\".\nlevel = \"info\"\n"));
+        assert!(example.contains("# Secret.\n# password = \"\"\n"));
+        assert!(!example.contains("changeme"));
+
+        let config = Config::try_new(&example).unwrap();
+        assert_eq!(config.get::<i64>("server.port"), Some(8080));
+        assert!(!config.has_key("debug"));
+        assert_eq!(
+            config.get::<Vec<String>>("labels.my key"),
+            Some(vec!["a".into()])
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_schema() {
+        use crate::validation::is_valid_email;
+
+        let schema = Schema::new()
+            .field(
+                Field::new("name")
+                    .required()
+                    .doc("Service name.")
+                    .length(1, 32),
+            )
+            .field(
+                Field::new("admin.email")
+                    .required()
+                    .pattern("email", is_valid_email),
+            )
+            .field(
+                Field::new("admin.password")
+                    .secret()
+                    .default_value("changeme"),
+            )
+            .field(Field::new("hosts").of_type(ValueType::Array).length(1, 4))
+            .field(
+                Field::new("log.level")
+                    .of_type(ValueType::String)
+                    .one_of(["info", "debug"])
+                    .default_value("info"),
+            )
+            .field(Field::new("started").of_type(ValueType::Datetime));
+
+        let json = schema.json_schema();
+        assert_eq!(
+            json["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert_eq!(json["required"], serde_json::json!(["name", "admin"]));
+        assert_eq!(json["properties"]["name"]["description"], "Service name.");
+        assert_eq!(json["properties"]["name"]["maxLength"], 32);
+        assert_eq!(json["properties"]["hosts"]["minItems"], 1);
+
+        let admin = &json["properties"]["admin"];
+        assert_eq!(admin["type"], "object");
+        assert_eq!(admin["required"], serde_json::json!(["email"]));
+        assert_eq!(admin["properties"]["email"]["format"], "email");
+        assert_eq!(admin["properties"]["password"]["writeOnly"], true);
+        assert!(admin["properties"]["password"].get("default").is_none());
+
+        let level = &json["properties"]["log"]["properties"]["level"];
+        assert_eq!(level["enum"], serde_json::json!(["info", "debug"]));
+        assert_eq!(level["default"], "info");
+        assert_eq!(json["properties"]["started"]["format"], "date-time");
+    }
+
+    #[test]
+    fn test_builder_template() {
+        let builder = ConfigBuilder::new()
+            .set_string("server.host", "localhost")
+            .set_int("server.port", 8080)
+            .set_string("server.api_token", "t0k3n");
+
+        let example = builder.schema().example_toml();
+        assert!(example.starts_with("[server]\n# Type: string. Secret.\n# api_token = \"\"\n"));
+        assert!(example.contains("# Type: integer.\nport = 8080\n"));
+        assert!(!example.contains("t0k3n"));
+        assert_eq!(
+            Config::try_new(&example)
+                .unwrap()
+                .get::<String>("server.host"),
+            Some("localhost".into())
+        );
+    }
+}
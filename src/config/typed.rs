@@ -0,0 +1,263 @@
+//! Typed configuration structs described by a schema.
+//!
+//! With the `derive` feature, `#[derive(ConfigSchema)]` implements
+//! [`ConfigSchema`] from field types, doc comments and `#[config(...)]`
+//! attributes:
+//!
+//! | Attribute | Effect |
+//! |-----------|--------|
+//! | `default = <expr>` | [`Field::default_value`](super::Field::default_value) |
+//! | `env = "VAR"` | [`Field::env`](super::Field::env) |
+//! | `range(min, max)`, `min = n`, `max = n` | numeric bounds |
+//! | `length(min, max)` | string or array length |
+//! | `one_of(a, b, ...)` | allowed values |
+//! | `pattern = path::to::check` | a `fn(&str) -> bool` check |
+//! | `secret` | [`Field::secret`](super::Field::secret) |
+//! | `rename = "key"` | key name, if not given by `#[serde(rename)]` |
+//! | `nested` | include the field type's own schema under its key |
+//!
+//! Fields are required unless they are `Option`s, have a default, or are
+//! covered by `#[serde(default)]`. The fields of an `Option` marked
+//! `nested` are added with [`Schema::nest_optional`]. Keys follow
+//! `#[serde(rename)]` and `#[serde(rename_all)]`; `#[serde(flatten)]` is
+//! rejected at compile time.
+
+use super::{ArrayMerge, Config, ConfigError, Schema};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+/// A configuration struct with a [`Schema`] describing its keys.
+///
+/// Implement [`schema`](ConfigSchema::schema), usually through
+/// `#[derive(ConfigSchema)]`, to get loading with defaults, environment
+/// overrides and validation.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::config::{Config, ConfigSchema, Field, Schema, ValueType};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// impl ConfigSchema for Server {
+///     fn schema() -> Schema {
+///         Schema::new()
+///             .field(Field::new("host").default_value("localhost"))
+///             .field(
+///                 Field::new("port")
+///                     .required()
+///                     .of_type(ValueType::Integer)
+///                     .range(1.0, 65535.0)
+///                     .env("SERVER_PORT"),
+///             )
+///     }
+/// }
+///
+/// let config = Config::new("port = 8080");
+/// let server = Server::from_config_with(&config, |var| {
+///     (var == "SERVER_PORT").then(|| "9000".to_string())
+/// })
+/// .unwrap();
+/// assert_eq!(server.host, "localhost");
+/// assert_eq!(server.port, 9000);
+///
+/// assert!(Server::from_config_with(&Config::new("port = 0"), |_| None).is_err());
+/// ```
+pub trait ConfigSchema: DeserializeOwned {
+    /// Keys, constraints, defaults and documentation of the struct.
+    fn schema() -> Schema;
+
+    /// Build the struct from a configuration.
+    ///
+    /// Schema defaults fill missing keys, then the declared environment
+    /// variables override the configuration. The result is validated
+    /// against the schema before being deserialized.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Validation`] listing every violation, or
+    /// [`ConfigError::ParseAt`] if deserialization fails.
+    fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        Self::from_config_with(config, |var| std::env::var(var).ok())
+    }
+
+    /// Like [`from_config`](ConfigSchema::from_config), reading
+    /// environment variables through `env`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Validation`] listing every violation, or
+    /// [`ConfigError::ParseAt`] if deserialization fails.
+    fn from_config_with(
+        config: &Config,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let schema = Self::schema();
+        let mut merged = schema.defaults();
+        merged.merge(config, ArrayMerge::Replace);
+        merged.merge(&schema.env_overrides_with(env), ArrayMerge::Replace);
+        merged.validate(&schema)?;
+        merged.parse()
+    }
+
+    /// Load the struct from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or as for
+    /// [`from_config`](ConfigSchema::from_config).
+    fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Self::from_config(&Config::from_file_strict(path)?)
+    }
+
+    /// Commented example TOML file documenting every key.
+    ///
+    /// See [`Schema::example_toml`].
+    #[must_use]
+    fn example_toml() -> String {
+        Self::schema().example_toml()
+    }
+
+    /// JSON Schema document describing the struct's configuration file.
+    ///
+    /// See [`Schema::json_schema`].
+    #[cfg(feature = "json")]
+    #[must_use]
+    fn json_schema() -> serde_json::Value {
+        Self::schema().json_schema()
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigSchema, Field, Source};
+    use crate::validation::is_valid_url;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, ConfigSchema)]
+    struct Database {
+        /// Connection URL.
+        #[config(pattern = is_valid_url, secret)]
+        url: String,
+        /// Maximum pool size.
+        #[config(default = 10, range(1, 100))]
+        pool: u32,
+    }
+
+    #[derive(Debug, Deserialize, ConfigSchema)]
+    struct AppConfig {
+        /// Service name.
+        #[config(length(1, 32))]
+        name: String,
+        #[config(default = "info", one_of("debug", "info", "warn"), env = "APP_LOG")]
+        log_level: String,
+        #[serde(rename = "tags")]
+        #[config(default = vec!["web"])]
+        labels: Vec<String>,
+        timeout: Option<f64>,
+        #[config(nested)]
+        database: Database,
+    }
+
+    #[test]
+    fn test_derived_schema() {
+        let schema = AppConfig::schema();
+        let keys: Vec<&str> = schema.fields().iter().map(Field::key).collect();
+        assert_eq!(
+            keys,
+            [
+                "name",
+                "log_level",
+                "tags",
+                "timeout",
+                "database.url",
+                "database.pool"
+            ]
+        );
+        assert!(schema.fields()[0].is_required());
+        assert!(!schema.fields()[3].is_required());
+        assert!(schema.fields()[4].is_secret());
+        assert_eq!(schema.fields()[4].description(), Some("Connection URL."));
+        assert_eq!(schema.fields()[1].env_var(), Some("APP_LOG"));
+        assert_eq!(
+            schema.defaults().source("database.pool"),
+            Some(&Source::Default)
+        );
+
+        let example = AppConfig::example_toml();
+        assert!(example.contains(
+            "# Service name.\n# Type: string. Required. Length: 1 to 32.\n# name = \"\"\n"
+        ));
+        assert!(example.contains("[database]\n# Connection URL.\n"));
+
+        #[cfg(feature = "json")]
+        assert_eq!(
+            AppConfig::json_schema()["properties"]["database"]["required"],
+            serde_json::json!(["url"])
+        );
+        assert!(
+            example
+                .contains("# Maximum pool size.\n# Type: integer. Range: 1 to 100.\npool = 10\n")
+        );
+    }
+
+    #[derive(Debug, Deserialize, ConfigSchema)]
+    #[serde(rename_all = "kebab-case")]
+    struct Service {
+        max_connections: u32,
+        #[serde(rename = "tls")]
+        #[config(nested)]
+        tls_config: Option<Database>,
+    }
+
+    #[test]
+    fn test_derived_serde_names_and_optional_tables() {
+        let schema = Service::schema();
+        let keys: Vec<&str> = schema.fields().iter().map(Field::key).collect();
+        assert_eq!(keys, ["max-connections", "tls.url", "tls.pool"]);
+        assert!(schema.fields()[0].is_required());
+        assert!(!schema.fields()[1].is_required());
+
+        let service =
+            Service::from_config_with(&Config::new("max-connections = 8"), |_| None).unwrap();
+        assert_eq!(service.max_connections, 8);
+        assert!(service.tls_config.is_none());
+    }
+
+    #[test]
+    fn test_load_derived() {
+        let config = Config::new(
+            r#"
+            name = "api"
+            [database]
+            url = "https://db.internal/app"
+        "#,
+        );
+        let app = AppConfig::from_config_with(&config, |var| {
+            (var == "APP_LOG").then(|| "debug".to_string())
+        })
+        .unwrap();
+        assert_eq!(app.log_level, "debug");
+        assert_eq!(app.labels, ["web"]);
+        assert_eq!(app.timeout, None);
+        assert_eq!(app.database.pool, 10);
+        assert_eq!(app.name, "api");
+        assert!(app.database.url.starts_with("https://"));
+
+        let invalid = Config::new(
+            "name = \"\"\nlog_level = \"trace\"\n[database]\nurl = \"nope\"\npool = 0\n",
+        );
+        let Err(ConfigError::Validation(errors)) = AppConfig::from_config_with(&invalid, |_| None)
+        else {
+            panic!("expected validation errors");
+        };
+        let keys: Vec<&str> = errors.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["name", "log_level", "database.url", "database.pool"]);
+    }
+}
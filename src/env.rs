@@ -166,12 +166,19 @@ pub fn get_string(key: &str) -> Option<String> {
 /// Everything else is false.
 #[must_use]
 pub fn get_bool(key: &str) -> bool {
-    env::var(key).is_ok_and(|v| {
-        v == "1"
-            || v.eq_ignore_ascii_case("true")
-            || v.eq_ignore_ascii_case("yes")
-            || v.eq_ignore_ascii_case("on")
-    })
+    env::var(key).is_ok_and(|v| parse_bool(&v))
+}
+
+/// Parse a string as a boolean using the same rules as [`get_bool`].
+///
+/// Recognizes: "true", "1", "yes", "on" as true (case-insensitive).
+/// Everything else is false.
+#[must_use]
+pub fn parse_bool(value: &str) -> bool {
+    value == "1"
+        || value.eq_ignore_ascii_case("true")
+        || value.eq_ignore_ascii_case("yes")
+        || value.eq_ignore_ascii_case("on")
 }
 
 /// Get an environment variable as a list, split by a delimiter.
@@ -188,17 +195,25 @@ pub fn get_bool(key: &str) -> bool {
 #[must_use]
 pub fn get_list(key: &str, delimiter: &str) -> Vec<String> {
     env::var(key)
-        .map(|v| {
-            v.split(delimiter)
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect()
-        })
+        .map(|v| parse_list(&v, delimiter))
         .unwrap_or_default()
 }
 
+/// Split a string into a list using the same rules as [`get_list`].
+///
+/// Items are trimmed and empty items are dropped.
+#[must_use]
+pub fn parse_list(value: &str, delimiter: &str) -> Vec<String> {
+    value
+        .split(delimiter)
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
 /// Check if an environment variable is set (and non-empty).
 #[must_use]
+#[allow(clippy::map_unwrap_or)]
 pub fn is_set(key: &str) -> bool {
     env::var(key).map(|v| !v.is_empty()).unwrap_or(false)
 }
@@ -310,6 +325,14 @@ mod tests {
         assert!(!is_set("NONEXISTENT_VAR_99999"));
     }
 
+    #[test]
+    fn test_parse_bool_and_list() {
+        assert!(parse_bool("YES"));
+        assert!(parse_bool("1"));
+        assert!(!parse_bool("off"));
+        assert_eq!(parse_list(" a, b ,,c ", ","), vec!["a", "b", "c"]);
+    }
+
     #[test]
     fn test_get_list_missing() {
         let list = get_list("NONEXISTENT_LIST_VAR", ",");
@@ -15,50 +15,378 @@
 //!     Ok(input.to_uppercase())
 //! }
 //! ```
+//!
+//! # Context
+//!
+//! [`ResultExt`] wraps any error with a message while keeping the original
+//! as its [`source`](std::error::Error::source), and [`Report`] prints the
+//! whole chain:
+//!
+//! ```rust
+//! use commons::error::{CommonResult, ResultExt};
+//!
+//! fn load(path: &str) -> CommonResult<String> {
+//!     std::fs::read_to_string(path).with_context(|| format!("Reading {path}"))
+//! }
+//!
+//! let err = load("/missing/app.toml").context("Loading settings").unwrap_err();
+//! let report = err.report().to_string();
+//! assert!(report.starts_with("Loading settings\n\nCaused by:\n    0: Reading /missing/app.toml\n    1: "));
+//! assert!(err.downcast_ref::<std::io::Error>().is_some());
+//! ```
+//!
+//! # Codes
+//!
+//! Every error has a stable [`ErrorCode`], such as `E_NOT_FOUND`, and an
+//! [`ErrorCategory`] mapping it to an HTTP status and a process exit code.
+//!
+//! # Serialization
+//!
+//! With the `config` feature, errors serialize into an
+//! [`ErrorReport`] holding the code, message, context frames, field errors
+//! and causes, and deserialize back into the original error.
+//!
+//! # Locations and backtraces
+//!
+//! With the opt-in `backtrace` feature, the constructors and [`ResultExt`]
+//! record the source location of their caller and a [`Backtrace`], which
+//! is only captured when `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` enables
+//! it. Without the feature nothing is recorded or allocated.
+//! [`Report::verbose`] includes both:
+//!
+//! ```rust
+//! use commons::error::CommonError;
+//!
+//! let err = CommonError::not_found("settings.toml");
+//! let report = err.report().verbose().to_string();
+//! # #[cfg(feature = "backtrace")]
+//! assert!(report.starts_with("Not found: settings.toml\n    at "));
+//! ```
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::Deref;
+use std::panic::Location;
+
+mod code;
+#[cfg(feature = "config")]
+mod report;
+
+pub use code::{ErrorCategory, ErrorCode};
+#[cfg(feature = "config")]
+#[cfg_attr(docsrs, doc(cfg(feature = "config")))]
+pub use report::{Detail, ErrorReport, FieldReport};
+
+/// Boxed error that can cross threads, used as the source of a
+/// [`CommonError::Context`].
+pub type BoxError = Box<dyn StdError + Send + Sync + 'static>;
+
+/// Message of a [`CommonError`], with where it was created and what
+/// caused it.
+///
+/// Dereferences to `str` and displays as the plain text. With the
+/// `backtrace` feature, creating a message records the caller's location
+/// and a [`Backtrace`].
+pub struct ErrorMessage {
+    text: String,
+    source: Option<BoxError>,
+    trace: Option<Box<Trace>>,
+}
+
+/// Where an [`ErrorMessage`] was created.
+#[derive(Debug)]
+struct Trace {
+    location: &'static Location<'static>,
+    backtrace: Backtrace,
+}
+
+impl ErrorMessage {
+    /// Create a message, recording the caller's location.
+    #[must_use]
+    #[track_caller]
+    pub fn new(text: impl Into<String>) -> Self {
+        let location = Location::caller();
+        Self {
+            text: text.into(),
+            source: None,
+            trace: cfg!(feature = "backtrace").then(|| {
+                Box::new(Trace {
+                    location,
+                    backtrace: Backtrace::capture(),
+                })
+            }),
+        }
+    }
+
+    /// Attach the error that caused this one.
+    #[must_use]
+    pub fn with_source(mut self, source: impl Into<BoxError>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// The error that caused this one, if any.
+    #[must_use]
+    pub fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| -> &(dyn StdError + 'static) { source })
+    }
+
+    /// The message text.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Convert into the message text.
+    #[must_use]
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Source location the message was created at, if recorded.
+    #[must_use]
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        self.trace.as_ref().map(|trace| trace.location)
+    }
+
+    /// Backtrace of where the message was created, if one was captured.
+    #[must_use]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.trace
+            .as_ref()
+            .map(|trace| &trace.backtrace)
+            .filter(|backtrace| backtrace.status() == BacktraceStatus::Captured)
+    }
+}
+
+impl Deref for ErrorMessage {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl AsRef<str> for ErrorMessage {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.text, f)
+    }
+}
+
+impl fmt::Debug for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.text, f)
+    }
+}
+
+impl PartialEq for ErrorMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
 
-use thiserror::Error;
+impl Eq for ErrorMessage {}
+
+impl PartialEq<str> for ErrorMessage {
+    fn eq(&self, other: &str) -> bool {
+        self.text == other
+    }
+}
+
+impl PartialEq<&str> for ErrorMessage {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
+
+impl PartialEq<String> for ErrorMessage {
+    fn eq(&self, other: &String) -> bool {
+        &self.text == other
+    }
+}
+
+impl From<String> for ErrorMessage {
+    #[track_caller]
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<&str> for ErrorMessage {
+    #[track_caller]
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
 
 /// Common error type for ecosystem projects.
 ///
 /// This enum covers the most common error cases encountered across projects.
 /// For project-specific errors, consider wrapping this or creating derived types.
-#[derive(Error, Debug)]
+#[derive(Debug)]
 pub enum CommonError {
     /// Invalid input provided to a function.
-    #[error("Invalid input: {0}")]
-    InvalidInput(String),
+    InvalidInput(ErrorMessage),
 
     /// Configuration error.
-    #[error("Configuration error: {0}")]
-    Config(String),
+    Config(ErrorMessage),
 
     /// IO operation failed.
-    #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+    Io(std::io::Error),
 
     /// Parse error for various formats.
-    #[error("Parse error: {0}")]
-    Parse(String),
+    Parse(ErrorMessage),
 
     /// Resource not found.
-    #[error("Not found: {0}")]
-    NotFound(String),
+    NotFound(ErrorMessage),
 
     /// Operation not permitted.
-    #[error("Permission denied: {0}")]
-    PermissionDenied(String),
+    PermissionDenied(ErrorMessage),
 
     /// Operation timed out.
-    #[error("Timeout: {0}")]
-    Timeout(String),
+    Timeout(ErrorMessage),
 
     /// External service error.
-    #[error("External error: {0}")]
-    External(String),
+    External(ErrorMessage),
 
     /// Generic error with custom message.
-    #[error("{0}")]
-    Custom(String),
+    Custom(ErrorMessage),
+
+    /// An error wrapped with a description of what was being done.
+    Context {
+        /// What was being done when the error occurred.
+        context: ErrorMessage,
+        /// The underlying error.
+        source: BoxError,
+    },
+}
+
+impl fmt::Display for CommonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidInput(msg) => write!(f, "Invalid input: {msg}"),
+            Self::Config(msg) => write!(f, "Configuration error: {msg}"),
+            Self::Io(error) => write!(f, "IO error: {error}"),
+            Self::Parse(msg) => write!(f, "Parse error: {msg}"),
+            Self::NotFound(msg) => write!(f, "Not found: {msg}"),
+            Self::PermissionDenied(msg) => write!(f, "Permission denied: {msg}"),
+            Self::Timeout(msg) => write!(f, "Timeout: {msg}"),
+            Self::External(msg) => write!(f, "External error: {msg}"),
+            Self::Custom(msg) => write!(f, "{msg}"),
+            Self::Context { context, .. } => write!(f, "{context}"),
+        }
+    }
+}
+
+impl StdError for CommonError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Context { source, .. } => Some(source.as_ref()),
+            _ => self.message().and_then(ErrorMessage::source),
+        }
+    }
+}
+
+impl From<std::io::Error> for CommonError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+// Converted errors get a fixed message describing the failure and keep
+// the original as the source, so its text appears once in a report.
+
+impl From<std::num::ParseIntError> for CommonError {
+    #[track_caller]
+    fn from(error: std::num::ParseIntError) -> Self {
+        Self::Parse(ErrorMessage::new("failed to parse integer").with_source(error))
+    }
+}
+
+impl From<std::num::ParseFloatError> for CommonError {
+    #[track_caller]
+    fn from(error: std::num::ParseFloatError) -> Self {
+        Self::Parse(ErrorMessage::new("failed to parse float").with_source(error))
+    }
+}
+
+#[cfg(feature = "config")]
+impl From<toml::de::Error> for CommonError {
+    #[track_caller]
+    fn from(error: toml::de::Error) -> Self {
+        Self::Parse(ErrorMessage::new("failed to parse TOML").with_source(error))
+    }
+}
+
+/// Parse failures become [`Parse`](CommonError::Parse) errors, invalid
+/// command-line overrides [`InvalidInput`](CommonError::InvalidInput)
+/// errors, and everything else [`Config`](CommonError::Config) errors.
+///
+/// The converted error keeps the [code](ErrorCode) of the original.
+#[cfg(feature = "config")]
+impl From<crate::config::ConfigError> for CommonError {
+    #[track_caller]
+    fn from(error: crate::config::ConfigError) -> Self {
+        use crate::config::ConfigError;
+        let (variant, text): (fn(ErrorMessage) -> Self, _) = match error {
+            ConfigError::FileRead(_) => (Self::Config, "failed to read configuration file"),
+            ConfigError::FileWrite(_) => (Self::Config, "failed to write configuration file"),
+            ConfigError::Parse(_) | ConfigError::ParseAt(_) => {
+                (Self::Parse, "failed to parse configuration")
+            }
+            ConfigError::MissingKey(_) => (Self::Config, "missing configuration key"),
+            ConfigError::TypeMismatch { .. } => {
+                (Self::Config, "configuration value has the wrong type")
+            }
+            ConfigError::UnsupportedFormat(_) => (Self::Config, "unsupported configuration format"),
+            ConfigError::Serialize(_) => (Self::Config, "failed to serialize configuration value"),
+            ConfigError::Include(_) => (Self::Config, "failed to include configuration file"),
+            ConfigError::Interpolation(_) => (Self::Config, "failed to interpolate configuration"),
+            ConfigError::Override(_) => (Self::InvalidInput, "invalid configuration override"),
+            ConfigError::Validation(_) => (Self::Config, "invalid configuration"),
+        };
+        variant(ErrorMessage::new(text).with_source(error))
+    }
+}
+
+/// Validation failures become [`InvalidInput`](CommonError::InvalidInput)
+/// errors.
+///
+/// The converted error keeps the [code](ErrorCode) of the original.
+#[cfg(feature = "validation")]
+impl From<crate::validation::ValidationError> for CommonError {
+    #[track_caller]
+    fn from(error: crate::validation::ValidationError) -> Self {
+        Self::InvalidInput(ErrorMessage::new("validation failed").with_source(error))
+    }
+}
+
+/// Unparseable variables become [`Parse`](CommonError::Parse) errors, and
+/// missing or empty ones [`Config`](CommonError::Config) errors.
+///
+/// The converted error keeps the [code](ErrorCode) of the original.
+#[cfg(feature = "env")]
+impl From<crate::env::EnvError> for CommonError {
+    #[track_caller]
+    fn from(error: crate::env::EnvError) -> Self {
+        use crate::env::EnvError;
+        let (variant, text): (fn(ErrorMessage) -> Self, _) = match error {
+            EnvError::NotSet(_) => (Self::Config, "environment variable not set"),
+            EnvError::Empty(_) => (Self::Config, "environment variable is empty"),
+            EnvError::ParseError { .. } => (Self::Parse, "failed to parse environment variable"),
+        };
+        variant(ErrorMessage::new(text).with_source(error))
+    }
 }
 
 /// Result type alias using [`CommonError`].
@@ -67,32 +395,136 @@ pub type CommonResult<T> = Result<T, CommonError>;
 impl CommonError {
     /// Create a new invalid input error.
     #[must_use]
+    #[track_caller]
     pub fn invalid_input(msg: impl Into<String>) -> Self {
-        Self::InvalidInput(msg.into())
+        Self::InvalidInput(ErrorMessage::new(msg))
     }
 
     /// Create a new configuration error.
     #[must_use]
+    #[track_caller]
     pub fn config(msg: impl Into<String>) -> Self {
-        Self::Config(msg.into())
+        Self::Config(ErrorMessage::new(msg))
     }
 
     /// Create a new parse error.
     #[must_use]
+    #[track_caller]
     pub fn parse(msg: impl Into<String>) -> Self {
-        Self::Parse(msg.into())
+        Self::Parse(ErrorMessage::new(msg))
     }
 
     /// Create a new not found error.
     #[must_use]
+    #[track_caller]
     pub fn not_found(msg: impl Into<String>) -> Self {
-        Self::NotFound(msg.into())
+        Self::NotFound(ErrorMessage::new(msg))
+    }
+
+    /// Create a new permission denied error.
+    #[must_use]
+    #[track_caller]
+    pub fn permission_denied(msg: impl Into<String>) -> Self {
+        Self::PermissionDenied(ErrorMessage::new(msg))
+    }
+
+    /// Create a new timeout error.
+    #[must_use]
+    #[track_caller]
+    pub fn timeout(msg: impl Into<String>) -> Self {
+        Self::Timeout(ErrorMessage::new(msg))
+    }
+
+    /// Create a new external service error.
+    #[must_use]
+    #[track_caller]
+    pub fn external(msg: impl Into<String>) -> Self {
+        Self::External(ErrorMessage::new(msg))
     }
 
     /// Create a new custom error.
     #[must_use]
+    #[track_caller]
     pub fn custom(msg: impl Into<String>) -> Self {
-        Self::Custom(msg.into())
+        Self::Custom(ErrorMessage::new(msg))
+    }
+
+    /// Wrap an error with context.
+    #[must_use]
+    #[track_caller]
+    pub fn context(context: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        Self::Context {
+            context: ErrorMessage::new(context),
+            source: source.into(),
+        }
+    }
+
+    /// The message of this error, unless it wraps an IO error.
+    #[must_use]
+    pub const fn message(&self) -> Option<&ErrorMessage> {
+        match self {
+            Self::InvalidInput(msg)
+            | Self::Config(msg)
+            | Self::Parse(msg)
+            | Self::NotFound(msg)
+            | Self::PermissionDenied(msg)
+            | Self::Timeout(msg)
+            | Self::External(msg)
+            | Self::Custom(msg)
+            | Self::Context { context: msg, .. } => Some(msg),
+            Self::Io(_) => None,
+        }
+    }
+
+    /// Source location this error was created at, if recorded.
+    #[must_use]
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        self.message().and_then(ErrorMessage::location)
+    }
+
+    /// Backtrace of where this error was created, if one was captured.
+    #[must_use]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.message().and_then(ErrorMessage::backtrace)
+    }
+
+    /// Iterate over this error and its chain of sources, outermost first.
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+        let error: &(dyn StdError + 'static) = self;
+        std::iter::successors(Some(error), |&error| error.source())
+    }
+
+    /// The innermost error of the chain.
+    #[must_use]
+    pub fn root_cause(&self) -> &(dyn StdError + 'static) {
+        self.chain().last().unwrap_or(self)
+    }
+
+    /// Find an error of type `E` in the chain, starting with this one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::error::{CommonError, ResultExt};
+    ///
+    /// let result: Result<u16, _> = "99999".parse::<u16>();
+    /// let err = result.context("Reading port").unwrap_err();
+    ///
+    /// assert!(err.downcast_ref::<std::num::ParseIntError>().is_some());
+    /// assert!(err.downcast_ref::<std::io::Error>().is_none());
+    /// ```
+    #[must_use]
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        self.chain().find_map(<dyn StdError>::downcast_ref)
+    }
+
+    /// Display the error followed by its chain of causes.
+    ///
+    /// The alternate form (`{:#}`) prints the chain on one line, separated
+    /// by colons. See [`Report::verbose`] for locations and backtraces.
+    #[must_use]
+    pub fn report(&self) -> Report<'_> {
+        Report::new(self)
     }
 
     /// Check if this is an input validation error.
@@ -108,19 +540,191 @@ impl CommonError {
     }
 }
 
-/// Extension trait for Result types.
+/// A context message for [`ResultExt::with_context`]: a string slice or
+/// `String`, or a closure that builds one only on failure.
+pub trait IntoContext {
+    /// Produce the message.
+    fn into_context(self) -> String;
+}
+
+impl IntoContext for &str {
+    fn into_context(self) -> String {
+        self.to_string()
+    }
+}
+
+impl IntoContext for String {
+    fn into_context(self) -> String {
+        self
+    }
+}
+
+impl IntoContext for &String {
+    fn into_context(self) -> String {
+        self.clone()
+    }
+}
+
+impl<F: FnOnce() -> C, C: fmt::Display> IntoContext for F {
+    fn into_context(self) -> String {
+        self().to_string()
+    }
+}
+
+/// Extension trait adding context to the errors of `Result`s.
+///
+/// The original error is kept as the
+/// [`source`](std::error::Error::source) of a [`CommonError::Context`],
+/// so context frames stack and the original can still be inspected with
+/// [`CommonError::downcast_ref`]. It is therefore implemented for errors
+/// that are `Send + Sync + 'static`.
 pub trait ResultExt<T> {
-    /// Convert any error to a `CommonError` with context.
+    /// Wrap the error with a context message.
     ///
     /// # Errors
     ///
-    /// Returns an error if the underlying result is an error.
-    fn with_context(self, context: &str) -> CommonResult<T>;
+    /// Returns a [`CommonError::Context`] if the underlying result is an
+    /// error.
+    fn context(self, context: impl fmt::Display) -> CommonResult<T>;
+
+    /// Wrap the error with a context message, which may be a closure
+    /// called only on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CommonError::Context`] if the underlying result is an
+    /// error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use commons::error::ResultExt;
+    ///
+    /// let path = "/missing/app.toml";
+    /// let err = std::fs::read_to_string(path)
+    ///     .with_context(|| format!("Reading {path}"))
+    ///     .with_context("Loading settings")
+    ///     .unwrap_err();
+    /// assert_eq!(err.chain().count(), 3);
+    /// ```
+    fn with_context(self, context: impl IntoContext) -> CommonResult<T>;
 }
 
-impl<T, E: std::error::Error> ResultExt<T> for Result<T, E> {
-    fn with_context(self, context: &str) -> CommonResult<T> {
-        self.map_err(|e| CommonError::Custom(format!("{context}: {e}")))
+impl<T, E: StdError + Send + Sync + 'static> ResultExt<T> for Result<T, E> {
+    #[track_caller]
+    fn context(self, context: impl fmt::Display) -> CommonResult<T> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(CommonError::context(context.to_string(), e)),
+        }
+    }
+
+    #[track_caller]
+    fn with_context(self, context: impl IntoContext) -> CommonResult<T> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(CommonError::context(context.into_context(), e)),
+        }
+    }
+}
+
+/// Displays an error together with its chain of causes.
+///
+/// ```text
+/// Loading settings
+///
+/// Caused by:
+///     0: Reading /etc/app.toml
+///     1: No such file or directory (os error 2)
+/// ```
+///
+/// The alternate form (`{:#}`) prints
+/// `Loading settings: Reading /etc/app.toml: No such file or directory`.
+///
+/// A [`verbose`](Report::verbose) report also shows where each
+/// [`CommonError`] in the chain was created, and the backtrace of the
+/// innermost one that captured it:
+///
+/// ```text
+/// Loading settings
+///     at src/main.rs:12:10
+///
+/// Caused by:
+///     0: Reading /etc/app.toml
+///            at src/settings.rs:40:14
+///     1: No such file or directory (os error 2)
+///
+/// Backtrace:
+///    0: ...
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Report<'a> {
+    error: &'a (dyn StdError + 'static),
+    verbose: bool,
+}
+
+impl<'a> Report<'a> {
+    /// Report any error.
+    #[must_use]
+    pub fn new(error: &'a (dyn StdError + 'static)) -> Self {
+        Self {
+            error,
+            verbose: false,
+        }
+    }
+
+    /// Include source locations and the backtrace, when recorded.
+    ///
+    /// Has no effect on the alternate one-line form.
+    #[must_use]
+    pub const fn verbose(mut self) -> Self {
+        self.verbose = true;
+        self
+    }
+
+    /// Location of an error in the chain, if verbose and recorded.
+    fn location(&self, error: &(dyn StdError + 'static)) -> Option<&'static Location<'static>> {
+        if !self.verbose {
+            return None;
+        }
+        error
+            .downcast_ref::<CommonError>()
+            .and_then(CommonError::location)
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        let causes = std::iter::successors(self.error.source(), |&error| error.source());
+        if f.alternate() {
+            for cause in causes {
+                write!(f, ": {cause}")?;
+            }
+            return Ok(());
+        }
+        if let Some(location) = self.location(self.error) {
+            write!(f, "\n    at {location}")?;
+        }
+        for (index, cause) in causes.enumerate() {
+            if index == 0 {
+                f.write_str("\n\nCaused by:")?;
+            }
+            write!(f, "\n    {index}: {cause}")?;
+            if let Some(location) = self.location(cause) {
+                write!(f, "\n           at {location}")?;
+            }
+        }
+        if self.verbose {
+            let backtrace = std::iter::successors(Some(self.error), |&error| error.source())
+                .filter_map(|error| error.downcast_ref::<CommonError>())
+                .filter_map(CommonError::backtrace)
+                .last();
+            if let Some(backtrace) = backtrace {
+                write!(f, "\n\nBacktrace:\n{backtrace}")?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -148,4 +752,151 @@ mod tests {
         let common_result = result.with_context("Reading file");
         assert!(common_result.is_err());
     }
+
+    #[test]
+    fn test_with_context_strings() {
+        let fail = || Err::<(), _>(std::io::Error::other("denied"));
+        let msg = String::from("Reading app.toml");
+
+        let err = fail().with_context(&msg).unwrap_err();
+        assert_eq!(err.to_string(), msg);
+        let err = fail().with_context(format!("Loading {msg}")).unwrap_err();
+        assert_eq!(err.to_string(), "Loading Reading app.toml");
+    }
+
+    #[test]
+    fn test_context_chain() {
+        let result: Result<(), std::io::Error> = Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no such file",
+        ));
+        let err = result
+            .context("Reading app.toml")
+            .with_context(|| format!("Loading {}", "settings"))
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Loading settings");
+        assert_eq!(err.chain().count(), 3);
+        assert_eq!(err.root_cause().to_string(), "no such file");
+        assert_eq!(
+            err.downcast_ref::<std::io::Error>()
+                .map(std::io::Error::kind),
+            Some(std::io::ErrorKind::NotFound)
+        );
+        assert!(matches!(
+            err.downcast_ref::<CommonError>(),
+            Some(CommonError::Context { .. })
+        ));
+
+        assert_eq!(
+            err.report().to_string(),
+            "Loading settings\n\nCaused by:\n    0: Reading app.toml\n    1: no such file"
+        );
+        assert_eq!(
+            format!("{:#}", err.report()),
+            "Loading settings: Reading app.toml: no such file"
+        );
+        assert_eq!(
+            CommonError::not_found("x").report().to_string(),
+            "Not found: x"
+        );
+    }
+
+    #[test]
+    fn test_conversions() {
+        fn port(value: &str) -> CommonResult<u16> {
+            Ok(value.parse::<u16>()?)
+        }
+
+        let err = port("http").unwrap_err();
+        assert!(matches!(err, CommonError::Parse(_)));
+        assert_eq!(err.to_string(), "Parse error: failed to parse integer");
+        assert!(err.downcast_ref::<std::num::ParseIntError>().is_some());
+        assert_eq!(
+            err.report().to_string(),
+            "Parse error: failed to parse integer\n\nCaused by:\n    0: invalid digit found in string"
+        );
+
+        let err = CommonError::from("x".parse::<f64>().unwrap_err());
+        assert!(err.downcast_ref::<std::num::ParseFloatError>().is_some());
+
+        let err = CommonError::from(std::io::Error::other("disk full"));
+        assert!(err.downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_crate_conversions() {
+        use crate::config::ConfigError;
+        use crate::env::EnvError;
+        use crate::validation::ValidationError;
+
+        let err = CommonError::from(ConfigError::MissingKey("port".into()));
+        assert!(matches!(err, CommonError::Config(_)));
+        assert_eq!(
+            format!("{:#}", err.report()),
+            "Configuration error: missing configuration key: Missing required config key: port"
+        );
+        assert!(matches!(
+            err.downcast_ref::<ConfigError>(),
+            Some(ConfigError::MissingKey(key)) if key == "port"
+        ));
+        let err = CommonError::from(ConfigError::Override("x".into()));
+        assert!(matches!(err, CommonError::InvalidInput(_)));
+        let err = CommonError::from(crate::config::Config::try_new("a = =").unwrap_err());
+        assert!(matches!(err, CommonError::Parse(_)));
+
+        let err = CommonError::from(ValidationError::Empty);
+        assert!(err.is_input_error());
+        assert_eq!(err.downcast_ref(), Some(&ValidationError::Empty));
+
+        let err = CommonError::from(EnvError::NotSet("HOME".into()));
+        assert!(matches!(err, CommonError::Config(_)));
+        let parse = EnvError::ParseError {
+            var: "PORT".into(),
+            value: "x".into(),
+            expected: "u16".into(),
+        };
+        assert!(matches!(CommonError::from(parse), CommonError::Parse(_)));
+
+        let err = CommonError::from(toml::from_str::<toml::Value>("a = =").unwrap_err());
+        assert!(err.downcast_ref::<toml::de::Error>().is_some());
+    }
+
+    #[test]
+    fn test_message() {
+        let err = CommonError::NotFound("file.txt".into());
+        let Some(msg) = err.message() else {
+            panic!("expected a message");
+        };
+        assert_eq!(msg, "file.txt");
+        assert_eq!(msg.len(), 8);
+        assert_eq!(format!("{err:?}"), "NotFound(\"file.txt\")");
+        assert!(
+            CommonError::Io(std::io::Error::other("x"))
+                .message()
+                .is_none()
+        );
+    }
+
+    #[cfg(feature = "backtrace")]
+    #[test]
+    fn test_location() {
+        let line = line!() + 1;
+        let err = CommonError::timeout("upstream");
+        let location = err.location().unwrap();
+        assert_eq!(location.file(), file!());
+        assert_eq!(location.line(), line);
+
+        let result: Result<(), CommonError> = Err(err);
+        let line = line!() + 1;
+        let err = result.context("Calling upstream").unwrap_err();
+        assert_eq!(err.location().map(Location::line), Some(line));
+
+        let report = err.report().verbose().to_string();
+        let expected = format!("Calling upstream\n    at {}:{line}:", file!());
+        assert!(report.starts_with(&expected), "{report}");
+        assert!(report.contains("\n    0: Timeout: upstream\n           at src/"));
+        assert!(!err.report().to_string().contains(" at "));
+    }
 }
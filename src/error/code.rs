@@ -0,0 +1,390 @@
+//! Stable error codes, categories, and their HTTP and exit code mappings.
+
+use super::CommonError;
+#[cfg(feature = "config")]
+use crate::config::ConfigError;
+#[cfg(feature = "env")]
+use crate::env::EnvError;
+#[cfg(feature = "validation")]
+use crate::validation::ValidationError;
+use std::error::Error as StdError;
+use std::fmt;
+
+/// A broad class of errors, mapped to an HTTP status and a process exit
+/// code.
+///
+/// The predefined categories use the `sysexits.h` exit codes. Applications
+/// can define their own with [`ErrorCategory::new`]:
+///
+/// ```rust
+/// use commons::error::ErrorCategory;
+///
+/// const QUOTA: ErrorCategory = ErrorCategory::new("quota", 429, 75);
+///
+/// assert_eq!(QUOTA.name(), "quota");
+/// assert_eq!(ErrorCategory::NOT_FOUND.http_status(), 404);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorCategory {
+    name: &'static str,
+    http_status: u16,
+    exit_code: u8,
+}
+
+impl ErrorCategory {
+    /// Invalid or malformed input: 400, `EX_DATAERR`.
+    pub const INPUT: Self = Self::new("input", 400, 65);
+    /// Invalid or missing configuration: 500, `EX_CONFIG`.
+    pub const CONFIG: Self = Self::new("config", 500, 78);
+    /// Filesystem or other IO failure: 500, `EX_IOERR`.
+    pub const IO: Self = Self::new("io", 500, 74);
+    /// Missing resource: 404, `EX_NOINPUT`.
+    pub const NOT_FOUND: Self = Self::new("not_found", 404, 66);
+    /// Operation not permitted: 403, `EX_NOPERM`.
+    pub const PERMISSION: Self = Self::new("permission", 403, 77);
+    /// Operation timed out: 504, `EX_TEMPFAIL`.
+    pub const TIMEOUT: Self = Self::new("timeout", 504, 75);
+    /// Failure of an external service: 502, `EX_UNAVAILABLE`.
+    pub const EXTERNAL: Self = Self::new("external", 502, 69);
+    /// Any other failure: 500, `EX_SOFTWARE`.
+    pub const INTERNAL: Self = Self::new("internal", 500, 70);
+
+    /// Define a category.
+    #[must_use]
+    pub const fn new(name: &'static str, http_status: u16, exit_code: u8) -> Self {
+        Self {
+            name,
+            http_status,
+            exit_code,
+        }
+    }
+
+    /// Name of the category.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// HTTP status code for errors of this category.
+    #[must_use]
+    pub const fn http_status(&self) -> u16 {
+        self.http_status
+    }
+
+    /// Process exit code for errors of this category.
+    #[must_use]
+    pub const fn exit_code(&self) -> u8 {
+        self.exit_code
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+/// Errors with a stable, machine-readable code.
+///
+/// Codes never change once published, so clients can match on them
+/// instead of on messages. The HTTP status and exit code default to those
+/// of the [`category`](ErrorCode::category).
+///
+/// # Example
+///
+/// ```rust
+/// use commons::error::{CommonError, ErrorCategory, ErrorCode};
+///
+/// let err = CommonError::not_found("user 42");
+/// assert_eq!(err.code(), "E_NOT_FOUND");
+/// assert_eq!(err.category(), ErrorCategory::NOT_FOUND);
+/// assert_eq!(err.http_status(), 404);
+/// assert_eq!(err.exit_code(), 66);
+/// ```
+pub trait ErrorCode {
+    /// Stable code identifying the error, such as `E_NOT_FOUND`.
+    fn code(&self) -> &'static str;
+
+    /// Category of the error.
+    fn category(&self) -> ErrorCategory;
+
+    /// HTTP status code to respond with.
+    fn http_status(&self) -> u16 {
+        self.category().http_status()
+    }
+
+    /// Process exit code to terminate with.
+    fn exit_code(&self) -> u8 {
+        self.category().exit_code()
+    }
+}
+
+/// View an error of one of this crate's types, or an IO error, as an
+/// [`ErrorCode`].
+pub(super) fn as_error_code<'a>(error: &'a (dyn StdError + 'static)) -> Option<&'a dyn ErrorCode> {
+    if let Some(error) = error.downcast_ref::<CommonError>() {
+        return Some(error);
+    }
+    if let Some(error) = error.downcast_ref::<std::io::Error>() {
+        return Some(error);
+    }
+    as_module_error_code(error)
+}
+
+/// View an error of another module of this crate, such as a
+/// `ConfigError`, as an [`ErrorCode`].
+fn as_module_error_code<'a>(error: &'a (dyn StdError + 'static)) -> Option<&'a dyn ErrorCode> {
+    #[cfg(not(any(feature = "config", feature = "env", feature = "validation")))]
+    let _ = error;
+    #[cfg(feature = "config")]
+    if let Some(error) = error.downcast_ref::<ConfigError>() {
+        return Some(error);
+    }
+    #[cfg(feature = "env")]
+    if let Some(error) = error.downcast_ref::<EnvError>() {
+        return Some(error);
+    }
+    #[cfg(feature = "validation")]
+    if let Some(error) = error.downcast_ref::<ValidationError>() {
+        return Some(error);
+    }
+    None
+}
+
+impl CommonError {
+    /// The error of another module this one was converted from, if any.
+    pub(super) fn converted_from(&self) -> Option<&(dyn StdError + 'static)> {
+        self.message()
+            .and_then(super::ErrorMessage::source)
+            .filter(|source| as_module_error_code(*source).is_some())
+    }
+
+    /// Code and category of the error this one was converted from.
+    fn converted_code(&self) -> Option<(&'static str, ErrorCategory)> {
+        self.converted_from()
+            .and_then(as_module_error_code)
+            .map(|error| (error.code(), error.category()))
+    }
+
+    /// Code and category of the nearest error with a code wrapped by a
+    /// [`Context`](CommonError::Context) error.
+    fn wrapped_code(&self) -> Option<(&'static str, ErrorCategory)> {
+        self.chain()
+            .skip(1)
+            .filter(|error| !matches!(error.downcast_ref::<Self>(), Some(Self::Context { .. })))
+            .find_map(as_error_code)
+            .map(|error| (error.code(), error.category()))
+    }
+}
+
+impl ErrorCode for CommonError {
+    /// The code of the variant. A [`Context`](CommonError::Context) error
+    /// takes the code of the error it wraps, or `E_CONTEXT` for errors from
+    /// outside this crate, and an error converted from another module's
+    /// error, such as a [`ConfigError`], takes the code of that error.
+    fn code(&self) -> &'static str {
+        if let Some((code, _)) = self.converted_code() {
+            return code;
+        }
+        match self {
+            Self::InvalidInput(_) => "E_INVALID_INPUT",
+            Self::Config(_) => "E_CONFIG",
+            Self::Io(_) => "E_IO",
+            Self::Parse(_) => "E_PARSE",
+            Self::NotFound(_) => "E_NOT_FOUND",
+            Self::PermissionDenied(_) => "E_PERMISSION_DENIED",
+            Self::Timeout(_) => "E_TIMEOUT",
+            Self::External(_) => "E_EXTERNAL",
+            Self::Custom(_) => "E_CUSTOM",
+            Self::Context { .. } => self.wrapped_code().map_or("E_CONTEXT", |(code, _)| code),
+        }
+    }
+
+    fn category(&self) -> ErrorCategory {
+        if let Some((_, category)) = self.converted_code() {
+            return category;
+        }
+        match self {
+            Self::InvalidInput(_) | Self::Parse(_) => ErrorCategory::INPUT,
+            Self::Config(_) => ErrorCategory::CONFIG,
+            Self::Io(_) => ErrorCategory::IO,
+            Self::NotFound(_) => ErrorCategory::NOT_FOUND,
+            Self::PermissionDenied(_) => ErrorCategory::PERMISSION,
+            Self::Timeout(_) => ErrorCategory::TIMEOUT,
+            Self::External(_) => ErrorCategory::EXTERNAL,
+            Self::Custom(_) => ErrorCategory::INTERNAL,
+            Self::Context { .. } => self
+                .wrapped_code()
+                .map_or(ErrorCategory::INTERNAL, |(_, category)| category),
+        }
+    }
+}
+
+impl ErrorCode for std::io::Error {
+    fn code(&self) -> &'static str {
+        "E_IO"
+    }
+
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::IO
+    }
+}
+
+#[cfg(feature = "config")]
+impl ErrorCode for ConfigError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::FileRead(_) => "E_CONFIG_READ",
+            Self::FileWrite(_) => "E_CONFIG_WRITE",
+            Self::Parse(_) | Self::ParseAt(_) => "E_CONFIG_PARSE",
+            Self::MissingKey(_) => "E_CONFIG_MISSING_KEY",
+            Self::TypeMismatch { .. } => "E_CONFIG_TYPE_MISMATCH",
+            Self::UnsupportedFormat(_) => "E_CONFIG_UNSUPPORTED_FORMAT",
+            Self::Serialize(_) => "E_CONFIG_SERIALIZE",
+            Self::Include(_) => "E_CONFIG_INCLUDE",
+            Self::Interpolation(_) => "E_CONFIG_INTERPOLATION",
+            Self::Override(_) => "E_CONFIG_OVERRIDE",
+            Self::Validation(_) => "E_CONFIG_INVALID",
+        }
+    }
+
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::FileRead(_) | Self::FileWrite(_) => ErrorCategory::IO,
+            Self::Serialize(_) => ErrorCategory::INTERNAL,
+            Self::Override(_) => ErrorCategory::INPUT,
+            _ => ErrorCategory::CONFIG,
+        }
+    }
+}
+
+#[cfg(feature = "env")]
+impl ErrorCode for EnvError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::NotSet(_) => "E_ENV_NOT_SET",
+            Self::ParseError { .. } => "E_ENV_PARSE",
+            Self::Empty(_) => "E_ENV_EMPTY",
+        }
+    }
+
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::CONFIG
+    }
+}
+
+#[cfg(feature = "validation")]
+impl ErrorCode for ValidationError {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "E_VALIDATION_EMPTY",
+            Self::TooShort { .. } => "E_VALIDATION_TOO_SHORT",
+            Self::TooLong { .. } => "E_VALIDATION_TOO_LONG",
+            Self::BelowMin { .. } => "E_VALIDATION_BELOW_MIN",
+            Self::AboveMax { .. } => "E_VALIDATION_ABOVE_MAX",
+            Self::InvalidPattern { .. } => "E_VALIDATION_INVALID_PATTERN",
+            Self::NotInSet { .. } => "E_VALIDATION_NOT_IN_SET",
+            Self::Custom(_) => "E_VALIDATION",
+        }
+    }
+
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::INPUT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ResultExt;
+
+    #[test]
+    fn test_codes() {
+        let cases = [
+            (CommonError::invalid_input("x"), "E_INVALID_INPUT", 400, 65),
+            (CommonError::parse("x"), "E_PARSE", 400, 65),
+            (CommonError::config("x"), "E_CONFIG", 500, 78),
+            (CommonError::Io(std::io::Error::other("x")), "E_IO", 500, 74),
+            (CommonError::not_found("x"), "E_NOT_FOUND", 404, 66),
+            (
+                CommonError::permission_denied("x"),
+                "E_PERMISSION_DENIED",
+                403,
+                77,
+            ),
+            (CommonError::timeout("x"), "E_TIMEOUT", 504, 75),
+            (CommonError::external("x"), "E_EXTERNAL", 502, 69),
+            (CommonError::custom("x"), "E_CUSTOM", 500, 70),
+        ];
+        for (err, code, status, exit) in cases {
+            assert_eq!(err.code(), code);
+            assert_eq!(err.http_status(), status, "{code}");
+            assert_eq!(err.exit_code(), exit, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_context_codes() {
+        let result: Result<(), CommonError> = Err(CommonError::timeout("db"));
+        let err = result
+            .context("Querying users")
+            .context("Handling request")
+            .unwrap_err();
+        assert_eq!(err.code(), "E_TIMEOUT");
+        assert_eq!(err.category(), ErrorCategory::TIMEOUT);
+
+        let result: Result<(), std::io::Error> = Err(std::io::Error::other("disk"));
+        assert_eq!(result.context("Saving").unwrap_err().code(), "E_IO");
+
+        let result: Result<u8, _> = "x".parse::<u8>();
+        let err = result.context("Reading level").unwrap_err();
+        assert_eq!(err.code(), "E_CONTEXT");
+        assert_eq!(err.category(), ErrorCategory::INTERNAL);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_crate_error_codes() {
+        let result: Result<(), _> = Err(ConfigError::MissingKey("port".into()));
+        let err = result.context("Starting server").unwrap_err();
+        assert_eq!(err.code(), "E_CONFIG_MISSING_KEY");
+        assert_eq!(err.exit_code(), 78);
+
+        assert_eq!(EnvError::NotSet("HOME".into()).code(), "E_ENV_NOT_SET");
+        assert_eq!(ValidationError::Empty.http_status(), 400);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_converted_error_codes() {
+        fn load() -> Result<(), CommonError> {
+            Err(ConfigError::FileRead("app.toml".into()))?
+        }
+
+        let err = load().unwrap_err();
+        assert!(matches!(err, CommonError::Config(_)));
+        assert_eq!(err.code(), "E_CONFIG_READ");
+        assert_eq!(err.category(), ErrorCategory::IO);
+        let direct: Result<(), _> = Err(ConfigError::FileRead("app.toml".into()));
+        assert_eq!(
+            direct.context("Starting").unwrap_err().exit_code(),
+            err.exit_code()
+        );
+        assert_eq!(
+            load().context("Starting").unwrap_err().code(),
+            "E_CONFIG_READ"
+        );
+
+        let err = CommonError::from(EnvError::Empty("HOME".into()));
+        assert_eq!(err.code(), "E_ENV_EMPTY");
+        let err = CommonError::from(ValidationError::TooShort { min: 3, actual: 1 });
+        assert_eq!(
+            (err.code(), err.http_status()),
+            ("E_VALIDATION_TOO_SHORT", 400)
+        );
+
+        // Errors from outside the crate keep the code of the variant.
+        let err = CommonError::from("x".parse::<u8>().unwrap_err());
+        assert_eq!(err.code(), "E_PARSE");
+    }
+}
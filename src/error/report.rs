@@ -0,0 +1,799 @@
+//! Serializable error reports.
+//!
+//! [`CommonError`] and the error types of the other modules serialize into
+//! the same [`ErrorReport`] shape, suitable for JSON APIs, TOML files and
+//! structured logs:
+//!
+//! ```json
+//! {
+//!   "code": "E_CONFIG_MISSING_KEY",
+//!   "message": "Missing required config key: port",
+//!   "context": ["Starting server"]
+//! }
+//! ```
+//!
+//! Deserializing a report restores the error it was built from, with the
+//! same code, message, context frames and field errors. Errors from outside
+//! this crate are restored as opaque errors carrying their messages.
+
+use super::code::as_error_code;
+use super::{BoxError, CommonError, ErrorMessage};
+use crate::config::{ConfigError, ParseError};
+use crate::env::EnvError;
+use crate::validation::ValidationError;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt;
+
+/// Names returned by `toml::Value::type_str`.
+const TOML_TYPES: [&str; 7] = [
+    "string", "integer", "float", "boolean", "datetime", "array", "table",
+];
+
+/// A value attached to an [`ErrorReport`] or [`FieldReport`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Detail {
+    /// A count or position.
+    Integer(u64),
+    /// A name or value.
+    Text(String),
+    /// A list of names or values.
+    List(Vec<String>),
+}
+
+/// Serializable description of an error.
+///
+/// # Example
+///
+/// ```rust
+/// use commons::error::{CommonError, ErrorReport, ResultExt};
+///
+/// let result: Result<(), _> = Err(CommonError::not_found("user 42"));
+/// let err = result.context("Loading profile").unwrap_err();
+///
+/// let report = ErrorReport::new(&err);
+/// assert_eq!(report.code(), "E_NOT_FOUND");
+/// assert_eq!(report.message(), "Not found: user 42");
+/// assert_eq!(report.context(), ["Loading profile"]);
+///
+/// let restored = CommonError::try_from(report).unwrap();
+/// assert_eq!(restored.report().to_string(), err.report().to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    code: String,
+    message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    context: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    causes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<FieldReport>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    details: BTreeMap<String, Detail>,
+}
+
+/// Serializable description of an error in one field of a larger value,
+/// such as a schema violation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldReport {
+    field: String,
+    code: String,
+    message: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    details: BTreeMap<String, Detail>,
+}
+
+impl ErrorReport {
+    /// Describe an error and its chain of sources.
+    ///
+    /// Leading [`Context`](CommonError::Context) frames are listed in
+    /// [`context`](ErrorReport::context); the error they wrap provides the
+    /// message, details and field errors, and its sources the
+    /// [`causes`](ErrorReport::causes). The code is that of the outermost
+    /// error, or `E_UNKNOWN` for errors from outside this crate. A
+    /// [`CommonError`] converted from another module's error takes its
+    /// details and field errors from that error.
+    #[must_use]
+    pub fn new(error: &(dyn StdError + 'static)) -> Self {
+        let code = as_error_code(error).map_or("E_UNKNOWN", |error| error.code());
+        let mut context = Vec::new();
+        let mut base = error;
+        while let Some(CommonError::Context {
+            context: frame,
+            source,
+        }) = base.downcast_ref::<CommonError>()
+        {
+            context.push(frame.to_string());
+            base = source.as_ref();
+        }
+
+        let mut report = Self {
+            code: code.to_string(),
+            message: base.to_string(),
+            context,
+            causes: std::iter::successors(base.source(), |&error| error.source())
+                .map(ToString::to_string)
+                .collect(),
+            fields: Vec::new(),
+            details: BTreeMap::new(),
+        };
+        let original = base
+            .downcast_ref::<CommonError>()
+            .and_then(CommonError::converted_from)
+            .unwrap_or(base);
+        if let Some(error) = original.downcast_ref::<ValidationError>() {
+            report.details = validation_details(error);
+        }
+        if let Some(error) = original.downcast_ref::<EnvError>() {
+            report.details = env_details(error);
+        }
+        if let Some(error) = original.downcast_ref::<ConfigError>() {
+            report.describe_config(error);
+        }
+        report
+    }
+
+    /// Stable code of the error, such as `E_NOT_FOUND`.
+    #[must_use]
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Message of the error, below any context frames.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Context frames, outermost first.
+    #[must_use]
+    pub fn context(&self) -> &[String] {
+        &self.context
+    }
+
+    /// Messages of the errors that caused this one, outermost first.
+    #[must_use]
+    pub fn causes(&self) -> &[String] {
+        &self.causes
+    }
+
+    /// Errors in individual fields.
+    #[must_use]
+    pub fn fields(&self) -> &[FieldReport] {
+        &self.fields
+    }
+
+    /// Structured values describing the error, by name.
+    #[must_use]
+    pub const fn details(&self) -> &BTreeMap<String, Detail> {
+        &self.details
+    }
+
+    /// Fill in the details and field errors of a [`ConfigError`].
+    fn describe_config(&mut self, error: &ConfigError) {
+        match error {
+            ConfigError::ParseAt(parse) => {
+                self.details = details([("reason", Detail::Text(parse.message().to_string()))]);
+                if let Some(path) = parse.path() {
+                    self.details.insert(
+                        "path".into(),
+                        Detail::Text(path.to_string_lossy().into_owned()),
+                    );
+                }
+                if let (Some(line), Some(column)) = (parse.line(), parse.column()) {
+                    self.details
+                        .insert("line".into(), Detail::Integer(line as u64));
+                    self.details
+                        .insert("column".into(), Detail::Integer(column as u64));
+                }
+                if let Some(snippet) = parse.snippet() {
+                    self.details
+                        .insert("snippet".into(), Detail::Text(snippet.to_string()));
+                }
+            }
+            ConfigError::TypeMismatch {
+                key,
+                expected,
+                actual,
+            } => {
+                self.details = details([
+                    ("key", Detail::Text(key.clone())),
+                    ("expected", Detail::Text(expected.clone())),
+                    ("actual", Detail::Text((*actual).to_string())),
+                ]);
+            }
+            ConfigError::Validation(errors) => {
+                self.fields = errors
+                    .iter()
+                    .map(|(key, error)| {
+                        let report = Self::new(error);
+                        FieldReport {
+                            field: key.clone(),
+                            code: report.code,
+                            message: report.message,
+                            details: report.details,
+                        }
+                    })
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+
+    /// Restore the error described by the report, below any context
+    /// frames.
+    fn restore_base(&self) -> BoxError {
+        if let Some(error) = restore_common(&self.code, &self.message, &self.causes) {
+            return Box::new(error);
+        }
+        if let Some(error) = self.restore_converted() {
+            return Box::new(error);
+        }
+        if let Some(error) = self.restore_config() {
+            return Box::new(error);
+        }
+        if let Some(error) = restore_env(&self.code, &self.details) {
+            return Box::new(error);
+        }
+        if let Some(error) = restore_validation(&self.code, &self.message, &self.details) {
+            return Box::new(error);
+        }
+        Box::new(Opaque::chain(
+            std::iter::once(&self.message).chain(&self.causes),
+        ))
+    }
+
+    /// Restore a [`CommonError`] converted from another module's error,
+    /// which is described by the first of the causes.
+    fn restore_converted(&self) -> Option<CommonError> {
+        let (message, causes) = self.causes.split_first()?;
+        let original = Self {
+            message: message.clone(),
+            causes: causes.to_vec(),
+            context: Vec::new(),
+            ..self.clone()
+        };
+        let error = original
+            .restore_config()
+            .map(CommonError::from)
+            .or_else(|| restore_env(&self.code, &self.details).map(CommonError::from))
+            .or_else(|| {
+                restore_validation(&self.code, message, &self.details).map(CommonError::from)
+            })?;
+        // A report of the original error itself has the same code.
+        (error.to_string() == self.message).then_some(error)
+    }
+
+    /// Restore a [`ConfigError`].
+    fn restore_config(&self) -> Option<ConfigError> {
+        let message = |prefix: &str| strip(&self.message, prefix).to_string();
+        Some(match self.code.as_str() {
+            "E_CONFIG_READ" => ConfigError::FileRead(message("Failed to read config file: ")),
+            "E_CONFIG_WRITE" => ConfigError::FileWrite(message("Failed to write config file: ")),
+            "E_CONFIG_MISSING_KEY" => {
+                ConfigError::MissingKey(message("Missing required config key: "))
+            }
+            "E_CONFIG_UNSUPPORTED_FORMAT" => {
+                ConfigError::UnsupportedFormat(message("Unsupported config format: "))
+            }
+            "E_CONFIG_SERIALIZE" => {
+                ConfigError::Serialize(message("Failed to serialize config value: "))
+            }
+            "E_CONFIG_INCLUDE" => ConfigError::Include(message("Config include failed: ")),
+            "E_CONFIG_INTERPOLATION" => {
+                ConfigError::Interpolation(message("Config interpolation failed: "))
+            }
+            "E_CONFIG_OVERRIDE" => ConfigError::Override(message("Invalid config override: ")),
+            "E_CONFIG_PARSE" => {
+                let Some(reason) = text(&self.details, "reason") else {
+                    return Some(ConfigError::Parse(message("Failed to parse config: ")));
+                };
+                let mut parse = ParseError::new(reason);
+                if let Some(path) = text(&self.details, "path") {
+                    parse = parse.with_path(path);
+                }
+                ConfigError::ParseAt(Box::new(parse.with_location(
+                    integer(&self.details, "line"),
+                    integer(&self.details, "column"),
+                    text(&self.details, "snippet"),
+                )))
+            }
+            "E_CONFIG_TYPE_MISMATCH" => {
+                let actual = text(&self.details, "actual")?;
+                ConfigError::TypeMismatch {
+                    key: text(&self.details, "key")?,
+                    expected: text(&self.details, "expected")?,
+                    actual: TOML_TYPES.into_iter().find(|name| *name == actual)?,
+                }
+            }
+            "E_CONFIG_INVALID" => ConfigError::Validation(
+                self.fields
+                    .iter()
+                    .map(|field| {
+                        restore_validation(&field.code, &field.message, &field.details)
+                            .map(|error| (field.field.clone(), error))
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            _ => return None,
+        })
+    }
+}
+
+impl FieldReport {
+    /// Name of the field.
+    #[must_use]
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Stable code of the error, such as `E_VALIDATION_TOO_SHORT`.
+    #[must_use]
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Message of the error.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Structured values describing the error, by name.
+    #[must_use]
+    pub const fn details(&self) -> &BTreeMap<String, Detail> {
+        &self.details
+    }
+}
+
+/// An error restored from a report, known only by its message.
+#[derive(Debug)]
+struct Opaque {
+    message: String,
+    source: Option<Box<Self>>,
+}
+
+impl Opaque {
+    /// Chain of errors with the given messages, outermost first.
+    fn chain(messages: impl DoubleEndedIterator<Item: ToString>) -> Self {
+        messages
+            .rev()
+            .fold(None, |source, message| {
+                Some(Box::new(Self {
+                    message: message.to_string(),
+                    source,
+                }))
+            })
+            .map_or_else(
+                || Self {
+                    message: String::new(),
+                    source: None,
+                },
+                |error| *error,
+            )
+    }
+}
+
+impl fmt::Display for Opaque {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl StdError for Opaque {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| -> &(dyn StdError + 'static) { source })
+    }
+}
+
+/// Message with a variant's display prefix removed.
+fn strip<'a>(message: &'a str, prefix: &str) -> &'a str {
+    message.strip_prefix(prefix).unwrap_or(message)
+}
+
+/// Text detail named `key`.
+fn text(details: &BTreeMap<String, Detail>, key: &str) -> Option<String> {
+    match details.get(key)? {
+        Detail::Text(text) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+/// Integer detail named `key`.
+fn integer(details: &BTreeMap<String, Detail>, key: &str) -> Option<usize> {
+    match details.get(key)? {
+        Detail::Integer(value) => usize::try_from(*value).ok(),
+        _ => None,
+    }
+}
+
+/// Details map from name and value pairs.
+fn details<const N: usize>(pairs: [(&str, Detail); N]) -> BTreeMap<String, Detail> {
+    pairs
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+}
+
+/// Message for a report that does not describe the expected error type.
+fn unrestorable(code: &str) -> String {
+    format!("cannot restore error with code `{code}`")
+}
+
+/// Restore a [`CommonError`] that is not a context frame.
+fn restore_common(code: &str, message: &str, causes: &[String]) -> Option<CommonError> {
+    let message = |prefix: &str| {
+        let message = ErrorMessage::new(strip(message, prefix));
+        if causes.is_empty() {
+            message
+        } else {
+            message.with_source(Opaque::chain(causes.iter()))
+        }
+    };
+    Some(match code {
+        "E_INVALID_INPUT" => CommonError::InvalidInput(message("Invalid input: ")),
+        "E_CONFIG" => CommonError::Config(message("Configuration error: ")),
+        "E_PARSE" => CommonError::Parse(message("Parse error: ")),
+        "E_NOT_FOUND" => CommonError::NotFound(message("Not found: ")),
+        "E_PERMISSION_DENIED" => CommonError::PermissionDenied(message("Permission denied: ")),
+        "E_TIMEOUT" => CommonError::Timeout(message("Timeout: ")),
+        "E_EXTERNAL" => CommonError::External(message("External error: ")),
+        "E_CUSTOM" => CommonError::Custom(message("")),
+        "E_IO" if causes.is_empty() => {
+            CommonError::Io(std::io::Error::other(message("IO error: ").into_string()))
+        }
+        "E_IO" => CommonError::Io(std::io::Error::other(Opaque::chain(causes.iter()))),
+        _ => return None,
+    })
+}
+
+impl TryFrom<ErrorReport> for CommonError {
+    type Error = ErrorReport;
+
+    /// Restore the error described by a report.
+    ///
+    /// Reports of [`CommonError`]s are restored losslessly. Reports of
+    /// other errors are restored only below context frames.
+    fn try_from(report: ErrorReport) -> Result<Self, ErrorReport> {
+        let Some((innermost, outer)) = report.context.split_last() else {
+            return restore_common(&report.code, &report.message, &report.causes)
+                .or_else(|| report.restore_converted())
+                .ok_or(report);
+        };
+        let error = Self::context(innermost.clone(), report.restore_base());
+        Ok(outer
+            .iter()
+            .rev()
+            .fold(error, |error, frame| Self::context(frame.clone(), error)))
+    }
+}
+
+impl Serialize for CommonError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorReport::new(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CommonError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(ErrorReport::deserialize(deserializer)?)
+            .map_err(|report| D::Error::custom(unrestorable(&report.code)))
+    }
+}
+
+/// Details of a [`ValidationError`], as listed in its report.
+fn validation_details(error: &ValidationError) -> BTreeMap<String, Detail> {
+    let count = |value: usize| Detail::Integer(value as u64);
+    match error {
+        ValidationError::TooShort { min, actual } => {
+            details([("min", count(*min)), ("actual", count(*actual))])
+        }
+        ValidationError::TooLong { max, actual } => {
+            details([("max", count(*max)), ("actual", count(*actual))])
+        }
+        ValidationError::BelowMin { min, actual } => details([
+            ("min", Detail::Text(min.clone())),
+            ("actual", Detail::Text(actual.clone())),
+        ]),
+        ValidationError::AboveMax { max, actual } => details([
+            ("max", Detail::Text(max.clone())),
+            ("actual", Detail::Text(actual.clone())),
+        ]),
+        ValidationError::InvalidPattern { pattern } => {
+            details([("pattern", Detail::Text(pattern.clone()))])
+        }
+        ValidationError::NotInSet { allowed } => {
+            details([("allowed", Detail::List(allowed.clone()))])
+        }
+        ValidationError::Empty | ValidationError::Custom(_) => BTreeMap::new(),
+    }
+}
+
+/// Restore a [`ValidationError`] from its code, message and details.
+fn restore_validation(
+    code: &str,
+    message: &str,
+    details: &BTreeMap<String, Detail>,
+) -> Option<ValidationError> {
+    Some(match code {
+        "E_VALIDATION_EMPTY" => ValidationError::Empty,
+        "E_VALIDATION_TOO_SHORT" => ValidationError::TooShort {
+            min: integer(details, "min")?,
+            actual: integer(details, "actual")?,
+        },
+        "E_VALIDATION_TOO_LONG" => ValidationError::TooLong {
+            max: integer(details, "max")?,
+            actual: integer(details, "actual")?,
+        },
+        "E_VALIDATION_BELOW_MIN" => ValidationError::BelowMin {
+            min: text(details, "min")?,
+            actual: text(details, "actual")?,
+        },
+        "E_VALIDATION_ABOVE_MAX" => ValidationError::AboveMax {
+            max: text(details, "max")?,
+            actual: text(details, "actual")?,
+        },
+        "E_VALIDATION_INVALID_PATTERN" => ValidationError::InvalidPattern {
+            pattern: text(details, "pattern")?,
+        },
+        "E_VALIDATION_NOT_IN_SET" => match details.get("allowed")? {
+            Detail::List(allowed) => ValidationError::NotInSet {
+                allowed: allowed.clone(),
+            },
+            _ => return None,
+        },
+        "E_VALIDATION" => ValidationError::Custom(message.to_string()),
+        _ => return None,
+    })
+}
+
+impl TryFrom<ErrorReport> for ValidationError {
+    type Error = ErrorReport;
+
+    fn try_from(report: ErrorReport) -> Result<Self, ErrorReport> {
+        restore_validation(&report.code, &report.message, &report.details).ok_or(report)
+    }
+}
+
+impl Serialize for ValidationError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorReport::new(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ValidationError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(ErrorReport::deserialize(deserializer)?)
+            .map_err(|report| D::Error::custom(unrestorable(&report.code)))
+    }
+}
+
+/// Details of an [`EnvError`], as listed in its report.
+fn env_details(error: &EnvError) -> BTreeMap<String, Detail> {
+    match error {
+        EnvError::NotSet(var) | EnvError::Empty(var) => {
+            details([("var", Detail::Text(var.clone()))])
+        }
+        EnvError::ParseError {
+            var,
+            value,
+            expected,
+        } => details([
+            ("var", Detail::Text(var.clone())),
+            ("value", Detail::Text(value.clone())),
+            ("expected", Detail::Text(expected.clone())),
+        ]),
+    }
+}
+
+/// Restore an [`EnvError`] from its code and details.
+fn restore_env(code: &str, details: &BTreeMap<String, Detail>) -> Option<EnvError> {
+    Some(match code {
+        "E_ENV_NOT_SET" => EnvError::NotSet(text(details, "var")?),
+        "E_ENV_EMPTY" => EnvError::Empty(text(details, "var")?),
+        "E_ENV_PARSE" => EnvError::ParseError {
+            var: text(details, "var")?,
+            value: text(details, "value")?,
+            expected: text(details, "expected")?,
+        },
+        _ => return None,
+    })
+}
+
+impl TryFrom<ErrorReport> for EnvError {
+    type Error = ErrorReport;
+
+    fn try_from(report: ErrorReport) -> Result<Self, ErrorReport> {
+        restore_env(&report.code, &report.details).ok_or(report)
+    }
+}
+
+impl Serialize for EnvError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorReport::new(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for EnvError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(ErrorReport::deserialize(deserializer)?)
+            .map_err(|report| D::Error::custom(unrestorable(&report.code)))
+    }
+}
+
+impl TryFrom<ErrorReport> for ConfigError {
+    type Error = ErrorReport;
+
+    fn try_from(report: ErrorReport) -> Result<Self, ErrorReport> {
+        report.restore_config().ok_or(report)
+    }
+}
+
+impl Serialize for ConfigError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorReport::new(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(ErrorReport::deserialize(deserializer)?)
+            .map_err(|report| D::Error::custom(unrestorable(&report.code)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ResultExt;
+
+    /// Serialize to TOML and back.
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T {
+        toml::from_str(&toml::to_string(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_common_round_trip() {
+        let errors = [
+            CommonError::invalid_input("empty name"),
+            CommonError::config("no profile"),
+            CommonError::parse("bad date"),
+            CommonError::not_found("user 42"),
+            CommonError::permission_denied("read only"),
+            CommonError::timeout("upstream"),
+            CommonError::external("bank API"),
+            CommonError::custom("oops"),
+            CommonError::Io(std::io::Error::other("disk full")),
+            CommonError::from("x".parse::<u8>().unwrap_err()),
+            CommonError::from(ConfigError::MissingKey("port".into())),
+            CommonError::from(ValidationError::TooShort { min: 3, actual: 1 }),
+            CommonError::from(EnvError::NotSet("HOME".into())),
+        ];
+        for err in errors {
+            let restored = round_trip(&err);
+            assert_eq!(restored.message(), err.message());
+            assert_eq!(restored.report().to_string(), err.report().to_string());
+            assert_eq!(ErrorReport::new(&restored), ErrorReport::new(&err));
+        }
+    }
+
+    #[test]
+    fn test_context_round_trip() {
+        let result: Result<u8, _> = "x".parse::<u8>();
+        let err = result
+            .context("Reading level")
+            .context("Loading settings")
+            .unwrap_err();
+
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.code(), "E_CONTEXT");
+        assert_eq!(report.context(), ["Loading settings", "Reading level"]);
+        assert_eq!(report.message(), "invalid digit found in string");
+
+        let restored = round_trip(&err);
+        assert_eq!(ErrorReport::new(&restored), report);
+        assert_eq!(restored.report().to_string(), err.report().to_string());
+    }
+
+    #[test]
+    fn test_unknown_code() {
+        let report: ErrorReport =
+            toml::from_str("code = \"E_BILLING\"\nmessage = \"card declined\"\n").unwrap();
+        assert_eq!(report.code(), "E_BILLING");
+        assert!(CommonError::try_from(report).is_err());
+        assert!(toml::from_str::<CommonError>("code = \"E_BILLING\"\nmessage = \"x\"\n").is_err());
+    }
+
+    #[test]
+    fn test_crate_errors_round_trip() {
+        let validation = [
+            ValidationError::Empty,
+            ValidationError::TooShort { min: 3, actual: 1 },
+            ValidationError::TooLong { max: 3, actual: 9 },
+            ValidationError::BelowMin {
+                min: "1".into(),
+                actual: "0".into(),
+            },
+            ValidationError::AboveMax {
+                max: "10".into(),
+                actual: "11".into(),
+            },
+            ValidationError::InvalidPattern {
+                pattern: "email".into(),
+            },
+            ValidationError::NotInSet {
+                allowed: vec!["a".into(), "b".into()],
+            },
+            ValidationError::Custom("must be even".into()),
+        ];
+        for error in &validation {
+            assert_eq!(&round_trip(error), error);
+        }
+
+        let env = [
+            EnvError::NotSet("HOME".into()),
+            EnvError::Empty("HOME".into()),
+            EnvError::ParseError {
+                var: "PORT".into(),
+                value: "x".into(),
+                expected: "u16".into(),
+            },
+        ];
+        for error in &env {
+            assert_eq!(&round_trip(error), error);
+        }
+
+        let config = [
+            crate::config::Config::try_new("name = \"app\"\nport = = 8080").unwrap_err(),
+            ConfigError::ParseAt(Box::new(
+                ParseError::new("no such key").with_path("app.toml"),
+            )),
+            ConfigError::Parse("invalid type: string, expected u16".into()),
+            ConfigError::MissingKey("port".into()),
+            ConfigError::TypeMismatch {
+                key: "port".into(),
+                expected: "integer".into(),
+                actual: "string",
+            },
+            ConfigError::Validation(vec![
+                ("name".into(), ValidationError::Empty),
+                (
+                    "tags".into(),
+                    ValidationError::TooLong { max: 3, actual: 4 },
+                ),
+            ]),
+        ];
+        for error in &config {
+            let restored = round_trip(error);
+            assert_eq!(restored.to_string(), error.to_string());
+            assert_eq!(ErrorReport::new(&restored), ErrorReport::new(error));
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_shape() {
+        let error = ConfigError::Validation(vec![("name".into(), ValidationError::Empty)]);
+        let result: Result<(), _> = Err(error);
+        let err = result.context("Starting server").unwrap_err();
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({
+                "code": "E_CONFIG_INVALID",
+                "message": "Invalid config: name: Value cannot be empty",
+                "context": ["Starting server"],
+                "fields": [{
+                    "field": "name",
+                    "code": "E_VALIDATION_EMPTY",
+                    "message": "Value cannot be empty"
+                }]
+            })
+        );
+    }
+}
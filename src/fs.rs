@@ -75,6 +75,7 @@ pub fn ensure_dir(path: impl AsRef<Path>) -> io::Result<()> {
 /// Always returns `false` on non-Linux platforms.
 #[must_use]
 #[cfg(target_os = "linux")]
+#[allow(clippy::map_unwrap_or)]
 pub fn is_wsl() -> bool {
     std::fs::read_to_string("/proc/version")
         .map(|v| v.to_ascii_lowercase().contains("microsoft"))
@@ -9,6 +9,7 @@
 //!
 //! - `config` - Configuration file loading and management (TOML)
 //! - `error` - Common error types and Result aliases
+//! - `backtrace` - Source location and backtrace capture for `error` (not in `full`)
 //! - `logging` - Simple structured logging
 //! - `time` - Date/time utilities and formatting
 //! - `collections` - Extended collection utilities (LRU cache)
@@ -17,6 +18,8 @@
 //! - `id` - ID generation (timestamp, random, UUID-like)
 //! - `env` - Environment variable helpers
 //! - `fs` - Cross-platform filesystem utilities
+//! - `json`, `yaml`, `ini` - Additional `config` file formats (not in `full`)
+//! - `derive` - `#[derive(ConfigSchema)]` for typed `config` structs (not in `full`)
 //!
 //! ## Quick Start
 //!
@@ -34,7 +37,7 @@
 //!
 //! ```toml
 //! [dependencies]
-//! commons = { version = "0.0.2", default-features = false, features = ["error", "time"] }
+//! commons = { version = "0.1.0", default-features = false, features = ["error", "time"] }
 //! ```
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
@@ -42,6 +45,10 @@
 #![deny(unsafe_code)]
 #![warn(clippy::all)]
 
+// Lets `#[derive(ConfigSchema)]` refer to `::commons` in this crate's tests.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as commons;
+
 #[cfg(feature = "config")]
 #[cfg_attr(docsrs, doc(cfg(feature = "config")))]
 pub mod config;
@@ -91,7 +98,7 @@ pub mod fs;
 /// ```
 pub mod prelude {
     #[cfg(feature = "error")]
-    pub use crate::error::{CommonError, CommonResult};
+    pub use crate::error::{CommonError, CommonResult, ErrorCode};
 
     #[cfg(feature = "config")]
     pub use crate::config::{Config, ConfigBuilder, ConfigError};
@@ -138,6 +145,6 @@ mod tests {
 
     #[test]
     fn test_version() {
-        assert_eq!(version(), "0.0.2");
+        assert_eq!(version(), "0.1.0");
     }
 }